@@ -0,0 +1,75 @@
+use std::ffi::OsString;
+use std::fs::{self, File};
+use std::io::{self, Write as _};
+use std::path::{Path, PathBuf};
+use std::process;
+
+use log::debug;
+
+
+/// Writes `content` to a temporary file next to `path`, then renames it over
+/// `path`. Renames within a directory are atomic, so anything reading `path`
+/// sees either the old or the new content.
+pub(crate) fn write<C>(path: &Path, content: C) -> io::Result<()>
+where
+    C: AsRef<[u8]>,
+{
+    let temp = temp_path(path);
+    let result = write_temp(path, &temp, content.as_ref())
+        .and_then(|()| fs::rename(&temp, path));
+
+    if result.is_err()
+        && let Err(e) = fs::remove_file(&temp)
+        && e.kind() != io::ErrorKind::NotFound
+    {
+        debug!("failed to remove temp file `{}`: {e}", temp.display());
+    }
+
+    result
+}
+
+fn write_temp(path: &Path, temp: &Path, content: &[u8]) -> io::Result<()> {
+    let mut file = File::create(temp)?;
+
+    file.write_all(content)?;
+    file.sync_all()?;
+
+    // keep the permissions of the file being replaced
+    if let Ok(metadata) = fs::metadata(path) {
+        fs::set_permissions(temp, metadata.permissions())?;
+    }
+
+    Ok(())
+}
+
+fn temp_path(path: &Path) -> PathBuf {
+    let mut name = OsString::from(".");
+    name.push(path.file_name().unwrap_or_default());
+    name.push(format!(".{}.tmp", process::id()));
+
+    path.with_file_name(name)
+}
+
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+
+    #[test]
+    fn replaces_without_leftovers() {
+        let dir = tempfile::tempdir().expect("failed to create temp dir");
+        let path = dir.path().join("cutiepro.toml");
+
+        fs::write(&path, "old").expect("failed to write file");
+        write(&path, "new").expect("failed to write atomically");
+
+        assert_eq!(fs::read_to_string(&path).ok().as_deref(), Some("new"));
+        assert_eq!(
+            fs::read_dir(dir.path()).map(Iterator::count).ok(),
+            Some(1),
+            "temp file should be renamed away"
+        );
+    }
+}
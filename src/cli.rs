@@ -1,34 +1,67 @@
 use std::fs;
+use std::num::NonZeroUsize;
+use std::path::PathBuf;
 
-use clap::{ArgAction, Parser};
+use clap::error::ErrorKind as ClapErrorKind;
+use clap::parser::ValueSource;
+use clap::{
+    ArgAction, ArgMatches, CommandFactory as _, FromArgMatches as _, Parser,
+    Subcommand,
+};
 use env_logger::Builder as LoggerBuilder;
+use indexmap::IndexMap;
 use log::{LevelFilter as LogLevelFilter, info};
 
-use crate::output::WriteMode;
-use crate::templates::Loader;
-use crate::{Result, config, render, themes};
+use crate::config::Location;
+use crate::import::{self, Format as ImportFormat};
+use crate::output::{MessageFormat, WriteMode, events};
+use crate::templates::{Loader, library};
+use crate::themes::{GraphFormat, RoleOrigin};
+use crate::{
+    Config, Error, Result, Theme, ThemeName, config, install, render, scaffold,
+    themes,
+};
 
 
 // TODO: better documentation
-// TODO: add `prune` flag
 #[expect(clippy::struct_excessive_bools, reason = "cli args")]
 #[derive(Debug, Clone, Parser)]
 #[command(name = "they", version, about, long_about = None)]
 struct Args {
+    #[command(subcommand)]
+    command: Option<Command>,
+
     /// Output more info per invocation (-v, -vv, -vvv)
-    #[arg(short, long, action = ArgAction::Count)]
+    #[arg(short, long, action = ArgAction::Count, global = true)]
     verbose: u8,
 
     /// Silence all output except errors
-    #[arg(short, long, conflicts_with = "verbose")]
+    #[arg(short, long, conflicts_with = "verbose", global = true)]
     quiet: bool,
 
+    /// Print one JSON object per event to stdout instead of log lines
+    #[arg(long, value_enum, default_value_t, global = true)]
+    message_format: MessageFormat,
+
+    /// Use this config file instead of looking for `theymer.toml`
+    #[arg(long, value_name = "PATH", global = true)]
+    config: Option<PathBuf>,
+
+    /// Use this directory as the project root (defaults to the directory of
+    /// `--config` or the closest one with a `theymer.toml`)
+    #[arg(long, value_name = "DIR", global = true)]
+    root: Option<PathBuf>,
+
     /// Don't overwrite existing files
     #[arg(short, long)]
     keep: bool,
 
     /// Delete current contents of output directory before rendering
-    #[arg(short, long, conflicts_with = "keep")]
+    #[arg(
+        short,
+        long,
+        conflicts_with_all = ["keep", "themes", "schemes", "templates"]
+    )]
     clean: bool,
 
     /// Overwrite all existing files, even user-modified ones
@@ -38,6 +71,198 @@ struct Args {
     /// Preview changes without writing them to disk
     #[arg(long, alias = "dry")]
     dry_run: bool,
+
+    /// Show a diff of every file that would change (requires `--dry-run`)
+    #[arg(long, requires = "dry_run")]
+    diff: bool,
+
+    /// Ask how to resolve each conflict with a user-modified file
+    #[arg(short, long, conflicts_with_all = ["keep", "force"])]
+    interactive: bool,
+
+    /// Delete previously rendered files that are no longer rendered
+    #[arg(long, conflicts_with_all = ["themes", "schemes", "templates"])]
+    prune: bool,
+
+    /// Stop at the first output that fails to render
+    #[arg(long, overrides_with = "keep_going")]
+    fail_fast: bool,
+
+    /// Render everything else when an output fails and report every failure
+    /// at the end (default)
+    #[arg(long, overrides_with = "fail_fast")]
+    keep_going: bool,
+
+    /// Roll back every file written by the run if any output fails
+    #[arg(long, conflicts_with_all = ["dry_run", "clean"])]
+    transactional: bool,
+
+    /// Render this many outputs at once (defaults to the number of CPUs)
+    #[arg(short, long, value_name = "N")]
+    jobs: Option<NonZeroUsize>,
+
+    /// Only render themes matching this glob (repeatable)
+    #[arg(long = "theme", value_name = "GLOB")]
+    themes: Vec<String>,
+
+    /// Only render schemes matching this glob (repeatable)
+    #[arg(long = "scheme", value_name = "GLOB")]
+    schemes: Vec<String>,
+
+    /// Only render templates whose path or any path component matches this
+    /// glob (repeatable)
+    #[arg(long = "template", value_name = "GLOB")]
+    templates: Vec<String>,
+}
+
+#[derive(Debug, Clone, Subcommand)]
+enum Command {
+    /// Render every output in memory and report errors without writing
+    Check,
+
+    /// Fail if any rendered output is missing, outdated or user-modified
+    Verify,
+
+    /// Print the context templates see for a theme and scheme
+    Inspect {
+        /// Theme to inspect
+        theme: String,
+
+        /// Scheme to inspect (defaults to the theme's only scheme)
+        scheme: Option<String>,
+
+        /// Also expose this swatch as `swatch`, like `SWATCH` templates do
+        #[arg(long)]
+        swatch: Option<String>,
+
+        /// Apply this template's directives, e.g. `render_as_ascii`
+        #[arg(long)]
+        template: Option<String>,
+
+        /// Output format
+        #[arg(long, value_enum, default_value_t)]
+        format: render::InspectFormat,
+    },
+
+    /// Show how a role resolves in a scheme, or export its role graph
+    Explain {
+        /// Role to explain, e.g. `syntax.keyword_function`
+        #[arg(required_unless_present = "graph")]
+        role: Option<String>,
+
+        /// Theme the scheme belongs to (defaults to the only theme)
+        #[arg(long)]
+        theme: Option<String>,
+
+        /// Scheme to explain (defaults to the theme's only scheme)
+        #[arg(long)]
+        scheme: Option<String>,
+
+        /// Export every role's dependency in this format instead
+        #[arg(long, value_enum, conflicts_with = "role")]
+        graph: Option<GraphFormat>,
+    },
+
+    /// List or eject templates from the built-in library
+    Templates {
+        #[command(subcommand)]
+        command: TemplatesCommand,
+    },
+
+    /// Turn an existing config file into a template by replacing colors from
+    /// a scheme's palette with role placeholders
+    Templatize {
+        /// File to templatize, e.g. a hand-made alacritty theme
+        file: PathBuf,
+
+        /// Scheme whose palette the file uses
+        #[arg(long)]
+        scheme: String,
+
+        /// Theme the scheme belongs to (defaults to the only theme)
+        #[arg(long)]
+        theme: Option<String>,
+
+        /// Where to write the template (defaults to `<file>.jinja`)
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+    },
+
+    /// Convert a color scheme from another format into scheme toml
+    Import {
+        /// File to import, e.g. a base16 yaml or kitty `.conf`
+        path: PathBuf,
+
+        /// Format of the file (detected from its extension by default)
+        #[arg(long, value_enum)]
+        format: Option<ImportFormat>,
+
+        /// File or directory to write the scheme to (prints it by default)
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+
+        /// Overwrite the output file if it already exists
+        #[arg(short, long, requires = "output")]
+        force: bool,
+    },
+
+    /// Create a `theymer.toml` in the current directory (or `--root`)
+    Init {
+        /// Create a project holding several themes under `themes/`
+        #[arg(long)]
+        polytheme: bool,
+    },
+
+    /// Create a new theme or scheme with every required role stubbed out
+    New {
+        #[command(subcommand)]
+        kind: NewCommand,
+    },
+
+    /// Copy or link rendered outputs into the directories in `[install]`
+    Install,
+
+    /// Remove every file placed by `install`
+    Uninstall,
+
+    /// Re-render affected outputs whenever themes, schemes, templates or
+    /// config change
+    Watch {
+        /// Delete previously rendered files that are no longer rendered
+        #[arg(long)]
+        prune: bool,
+    },
+}
+
+#[derive(Debug, Clone, Subcommand)]
+enum TemplatesCommand {
+    /// List the built-in library templates and whether they're in use
+    List,
+
+    /// Copy a library template into the project's templates directory for
+    /// customizing
+    Eject {
+        /// Library template to eject, e.g. `helix`
+        name: String,
+    },
+}
+
+#[derive(Debug, Clone, Subcommand)]
+enum NewCommand {
+    /// Create `themes/<name>/` with a base `theme.toml`
+    Theme {
+        /// Name of the theme
+        name: String,
+    },
+
+    /// Create a scheme file in a theme's schemes directory
+    Scheme {
+        /// Theme to add the scheme to
+        theme: String,
+
+        /// Name of the scheme
+        name: String,
+    },
 }
 
 impl Args {
@@ -51,18 +276,235 @@ impl Args {
             WriteMode::Smart
         }
     }
+
+    const fn render_options(&self) -> render::Options {
+        render::Options {
+            write_mode: self.write_mode(),
+            dry_run: self.dry_run,
+            diff: self.diff,
+            prune: self.prune,
+            interactive: self.interactive,
+            fail_fast: self.fail_fast,
+            transactional: self.transactional,
+            jobs: self.jobs,
+        }
+    }
+
+    /// Options for `watch`, whose own `--prune` stands in for the top-level
+    /// one.
+    const fn watch_options(&self, prune: bool) -> render::Options {
+        render::Options {
+            prune,
+            ..self.render_options()
+        }
+    }
 }
 
 
 pub fn run() -> Result<()> {
-    let cli = Args::parse();
+    let matches = Args::command().get_matches();
+
+    reject_render_flags(&matches);
+
+    let cli = Args::from_arg_matches(&matches).unwrap_or_else(|e| e.exit());
 
-    init_logger(cli.verbose, cli.quiet);
+    let json = cli.message_format == MessageFormat::Json;
+
+    // log lines would only get in the way of events unless asked for
+    init_logger(cli.verbose, cli.quiet || (json && cli.verbose == 0));
+    events::init(cli.message_format);
+
+    let rendered = renders(cli.command.as_ref());
+    let result = dispatch(cli);
+
+    events::finish(&result, rendered);
+
+    result
+}
+
+
+const fn renders(command: Option<&Command>) -> bool {
+    matches!(
+        command,
+        None | Some(Command::Check | Command::Verify | Command::Watch { .. })
+    )
+}
+
+
+fn dispatch(cli: Args) -> Result<()> {
+    // neither importing nor creating a project needs an existing one
+    match &cli.command {
+        Some(Command::Import {
+            path,
+            format,
+            output,
+            force,
+        }) => return import::run(path, *format, output.as_deref(), *force),
+        Some(Command::Init { polytheme }) => {
+            return scaffold::init(cli.root.as_deref(), *polytheme);
+        }
+        _ => {}
+    }
+
+    let config = config::load(&Location {
+        config: cli.config.clone(),
+        root: cli.root.clone(),
+    })?;
+
+    // scaffolding has to run before loading, which fails on half-made
+    // themes, and installing only needs what was already rendered
+    match &cli.command {
+        Some(Command::New { kind }) => {
+            return match kind {
+                NewCommand::Theme { name } => scaffold::theme(name, &config),
+                NewCommand::Scheme { theme, name } => {
+                    scaffold::scheme(theme, name, &config)
+                }
+            };
+        }
+        Some(Command::Install) => return install::install(&config),
+        Some(Command::Uninstall) => return install::uninstall(&config),
+        _ => {}
+    }
 
-    let config = config::load()?;
     let templates = Loader::init(&config)?;
-    let themes = themes::load_all(&config)?;
+    let (themes, failures) = load_themes(&config, cli.command.as_ref())?;
+
+    match cli.command {
+        Some(Command::Check) => {
+            render::check(&templates, &themes, &config, failures)
+        }
+        Some(Command::Verify) => {
+            render::verify(&templates, &themes, &config, failures)
+        }
+        Some(Command::Inspect {
+            theme,
+            scheme,
+            swatch,
+            template,
+            format,
+        }) => render::inspect(
+            &templates,
+            &themes,
+            &config,
+            render::InspectTarget {
+                theme: &theme,
+                scheme: scheme.as_deref(),
+                swatch: swatch.as_deref(),
+                template: template.as_deref(),
+            },
+            format,
+        ),
+        Some(Command::Explain {
+            role,
+            theme,
+            scheme,
+            graph,
+        }) => explain(
+            &themes,
+            role.as_deref(),
+            theme.as_deref(),
+            scheme.as_deref(),
+            graph,
+        ),
+        Some(Command::Templates { command }) => {
+            templates_command(&command, &config)
+        }
+        Some(Command::Templatize {
+            file,
+            scheme,
+            theme,
+            output,
+        }) => render::templatize(&themes, render::TemplatizeTarget {
+            file: &file,
+            theme: theme.as_deref(),
+            scheme: &scheme,
+            output: output.as_deref(),
+        }),
+        Some(Command::Watch { prune }) => {
+            let options = cli.watch_options(prune);
+
+            render::watch(config, templates, themes, options, failures)
+        }
+        Some(
+            Command::Import { .. }
+            | Command::Init { .. }
+            | Command::New { .. }
+            | Command::Install
+            | Command::Uninstall,
+        ) => Err(Error::InternalBug {
+            module: "cli",
+            reason: "command should have been handled before loading"
+                .to_owned(),
+        }),
+        None => render_all(&cli, &templates, &themes, &config, failures),
+    }
+}
+
+
+/// Loads every theme that loads. Rendering reports the broken ones along
+/// with the outputs that failed, anything else reports them right away.
+fn load_themes(
+    config: &Config,
+    command: Option<&Command>,
+) -> Result<(IndexMap<ThemeName, Theme>, render::Failures)> {
+    let mut failures = render::Failures::default();
+    let themes = themes::load_all(config, &mut failures)?;
+
+    if !renders(command) {
+        failures.report();
+    }
+
+    Ok((themes, failures))
+}
+
+
+// global flags like `-v` must stay usable before a subcommand, which
+// `args_conflicts_with_subcommands` doesn't allow, so every other top-level
+// flag is rejected here instead
+fn reject_render_flags(matches: &ArgMatches) {
+    let Some((name, _)) = matches.subcommand() else {
+        return;
+    };
+
+    // watching writes each output like a render does, but one at a time and
+    // with no run to roll back or stop early
+    let allowed: &[&str] = if name == "watch" {
+        &["keep", "force", "dry_run", "diff", "interactive"]
+    } else {
+        &[]
+    };
+
+    let mut command = Args::command();
+    let flag = command
+        .get_arguments()
+        .filter(|arg| {
+            !arg.is_global_set() && !allowed.contains(&arg.get_id().as_str())
+        })
+        .find(|arg| {
+            matches.value_source(arg.get_id().as_str())
+                == Some(ValueSource::CommandLine)
+        })
+        .map(|arg| arg.get_long().unwrap_or_else(|| arg.get_id().as_str()));
+
+    if let Some(flag) = flag {
+        command
+            .error(
+                ClapErrorKind::ArgumentConflict,
+                format!("`--{flag}` can't be combined with `{name}`"),
+            )
+            .exit();
+    }
+}
 
+
+fn render_all(
+    cli: &Args,
+    templates: &Loader,
+    themes: &IndexMap<ThemeName, Theme>,
+    config: &Config,
+    failures: render::Failures,
+) -> Result<()> {
     if cli.clean {
         let render = &config.project.render_all_into;
         if let Some(dir) = render
@@ -77,7 +519,125 @@ pub fn run() -> Result<()> {
         }
     }
 
-    render::all(&templates, &themes, &config, cli.write_mode(), cli.dry_run)?;
+    let selection =
+        render::Selection::new(&cli.themes, &cli.schemes, &cli.templates)?;
+
+    render::all(
+        templates,
+        themes,
+        &selection,
+        config,
+        cli.render_options(),
+        failures,
+    )?;
+
+    Ok(())
+}
+
+
+fn templates_command(
+    command: &TemplatesCommand,
+    config: &Config,
+) -> Result<()> {
+    match command {
+        TemplatesCommand::List => {
+            let selected = library::select(&config.library.templates)?;
+            let width = library::LIBRARY
+                .iter()
+                .map(|entry| entry.name.len())
+                .max()
+                .unwrap_or_default();
+
+            for entry in library::LIBRARY {
+                let ejected = entry
+                    .files
+                    .iter()
+                    .all(|file| config.dirs.templates.join(file.path).exists());
+                let status = if ejected {
+                    "ejected"
+                } else if selected.iter().any(|e| e.name == entry.name) {
+                    "enabled"
+                } else {
+                    ""
+                };
+
+                events::print(&format!(
+                    "{:width$}  {status:7}  {}",
+                    entry.name,
+                    entry
+                        .files
+                        .iter()
+                        .map(|file| file.path)
+                        .collect::<Vec<_>>()
+                        .join(", ")
+                ));
+            }
+        }
+        TemplatesCommand::Eject { name } => {
+            for path in library::eject(name, &config.dirs.templates)? {
+                events::print(&format!("ejected `{}`", path.display()));
+            }
+        }
+    }
+
+    Ok(())
+}
+
+
+fn explain(
+    themes: &IndexMap<ThemeName, Theme>,
+    role: Option<&str>,
+    theme: Option<&str>,
+    scheme: Option<&str>,
+    graph: Option<GraphFormat>,
+) -> Result<()> {
+    let (theme, scheme) = themes::find(themes, theme, scheme)?;
+
+    if let Some(format) = graph {
+        events::print(&themes::role_graph(scheme, format)?);
+
+        return Ok(());
+    }
+
+    let Some(role) = role else {
+        return Ok(());
+    };
+    let role = role.parse().map_err(Error::Role)?;
+    let resolved =
+        scheme
+            .resolved_roles
+            .get(&role)
+            .ok_or_else(|| Error::InternalBug {
+                module: "cli",
+                reason: format!("role `{role}` missing from resolved roles"),
+            })?;
+
+    events::print(&format!(
+        "`{role}` resolves to {} (`${}`) in `{}/{}`:",
+        resolved.hex, resolved.swatch, theme.name, scheme.name
+    ));
+
+    let steps = themes::explain_role(scheme, role)?;
+    let width = steps
+        .iter()
+        .map(|step| step.role.as_str().len())
+        .max()
+        .unwrap_or_default();
+
+    for step in steps {
+        let note = match step.origin {
+            RoleOrigin::Explicit => "",
+            RoleOrigin::Inherited => "  (from `theme.toml`)",
+            RoleOrigin::Fallback => "  (unset, falls back to base role)",
+        };
+
+        events::print(&format!(
+            "  {:width$}  {:9}  -> {}{note}",
+            step.role.as_str(),
+            step.origin,
+            step.target
+        ));
+    }
 
     Ok(())
 }
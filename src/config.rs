@@ -5,8 +5,9 @@ use std::{env, fs, io};
 
 use indexmap::IndexMap;
 use log::debug;
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
 
+use crate::diagnostics::{Diagnostic, Source};
 use crate::extensions::Merge as _;
 
 
@@ -21,24 +22,24 @@ pub(crate) enum Error {
     #[error("failed to find `{FILENAME}` in `{cwd}` or any parent directory")]
     NoProjectRoot { cwd: String },
 
-    #[error("failed to read `{FILENAME}`: {src}")]
-    Reading { src: io::Error },
+    #[error("failed to read `{path}`: {src}")]
+    Reading { path: String, src: io::Error },
 
-    #[error("failed to parse `{FILENAME}`: {src}")]
-    Parsing { src: Box<toml::de::Error> },
+    #[error("failed to get the working directory: {src}")]
+    WorkingDir { src: io::Error },
+
+    #[error("failed to parse `{path}`: {}{diagnostic}", src.message())]
+    Parsing {
+        path: String,
+        src: Box<toml::de::Error>,
+        diagnostic: Box<Diagnostic>,
+    },
 
     #[error("failed to expand path `{path}`: {src}")]
     ExpandingPath {
         path: String,
         src: shellexpand::LookupError<env::VarError>,
     },
-
-    #[error("failed to move from `{cwd}` to project root `{root}`: {src}")]
-    ChangingDir {
-        cwd: String,
-        root: String,
-        src: io::Error,
-    },
 }
 
 
@@ -49,6 +50,8 @@ pub struct Config {
     pub strip_directives: Vec<Vec<String>>,
     pub project: ResolvedProject,
     pub dirs: ResolvedDirs,
+    pub library: Library,
+    pub install: ResolvedInstall,
 
     #[serde(rename(serialize = "provider"))]
     pub providers: Vec<Provider>,
@@ -62,6 +65,8 @@ struct Raw {
     pub strip_directives: Vec<Vec<String>>,
     pub project: Option<Project>,
     pub dirs: Dirs,
+    pub library: Library,
+    pub install: Install,
 
     #[serde(rename(serialize = "provider"))]
     pub providers: Vec<Provider>,
@@ -75,6 +80,8 @@ impl Default for Raw {
             strip_directives: vec![vec!["#:tombi".to_owned()]],
             project: None,
             dirs: Dirs::default(),
+            library: Library::default(),
+            install: Install::default(),
             providers: default_providers(),
         }
     }
@@ -87,6 +94,7 @@ pub struct ResolvedProject {
     pub r#type: ProjectType,
     pub render_all_into: Option<PathBuf>,
     pub root: PathBuf,
+    pub config_file: PathBuf,
 }
 
 #[derive(Debug, Deserialize)]
@@ -102,7 +110,7 @@ struct Project {
     clippy::exhaustive_enums,
     reason = "unlikely to add more project types"
 )]
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 pub enum ProjectType {
     Monotheme,
     Polytheme,
@@ -148,8 +156,51 @@ impl Default for Dirs {
 }
 
 
+/// Templates from the embedded library to render alongside the project's
+/// own, selected by name or path glob.
+#[non_exhaustive]
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+#[serde(default)]
+pub struct Library {
+    pub templates: Vec<String>,
+}
+
+
+/// Where `they install` places rendered outputs, keyed by a glob matching
+/// the templates they were rendered from.
+#[non_exhaustive]
+#[derive(Debug, Default, Deserialize)]
+pub struct ResolvedInstall {
+    pub mode: InstallMode,
+    pub paths: IndexMap<String, PathBuf>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+#[serde(default)]
+struct Install {
+    pub mode: InstallMode,
+    pub paths: IndexMap<String, String>,
+}
+
+#[expect(
+    clippy::exhaustive_enums,
+    reason = "a file is either copied or linked"
+)]
+#[derive(
+    Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize,
+)]
+#[serde(rename_all = "lowercase")]
+pub enum InstallMode {
+    #[default]
+    Copy,
+    Symlink,
+}
+
+
 #[non_exhaustive]
-#[derive(Debug, Clone, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(deny_unknown_fields)]
 pub struct Provider {
     pub host: String,
@@ -222,26 +273,77 @@ fn merge_providers_with_defaults(user_providers: &[Provider]) -> Vec<Provider> {
 }
 
 
-pub(crate) fn load() -> Result<Config> {
-    let cwd = env::current_dir().map_err(|src| Error::Reading { src })?;
+/// Where to load a project from. Without either, the root is the closest
+/// directory to the working directory with a `theymer.toml`.
+#[non_exhaustive]
+#[derive(Debug, Default, Clone)]
+pub(crate) struct Location {
+    /// Config file to use instead of `<root>/theymer.toml`.
+    pub config: Option<PathBuf>,
 
-    let root = find_project_root(&cwd)?;
+    /// Project root, defaulting to the directory of `config`.
+    pub root: Option<PathBuf>,
+}
 
-    debug!("using project root `{}`", root.display());
+impl Location {
+    /// The location `config` was loaded from, for loading it again.
+    pub(crate) fn of(config: &Config) -> Self {
+        Self {
+            config: Some(config.project.config_file.clone()),
+            root: Some(config.project.root.clone()),
+        }
+    }
+
+    /// Resolves the project root and config file to absolute paths.
+    fn resolve(&self) -> Result<(PathBuf, PathBuf)> {
+        let cwd =
+            env::current_dir().map_err(|src| Error::WorkingDir { src })?;
+
+        match (&self.config, &self.root) {
+            (Some(file), root) => {
+                let file = cwd.join(file);
+                let root = root.as_ref().map_or_else(
+                    || {
+                        file.parent()
+                            .map_or_else(|| cwd.clone(), Path::to_path_buf)
+                    },
+                    |root| cwd.join(root),
+                );
+
+                Ok((root, file))
+            }
+            (None, Some(root)) => {
+                let root = cwd.join(root);
+                let file = root.join(FILENAME);
+
+                Ok((root, file))
+            }
+            (None, None) => {
+                let root = find_project_root(&cwd)?;
+                let file = root.join(FILENAME);
+
+                Ok((root, file))
+            }
+        }
+    }
+}
 
-    let path = root.join(FILENAME);
-    let content =
-        fs::read_to_string(&path).map_err(|src| Error::Reading { src })?;
 
-    // FIXME: remove once all code is updated to use absolute paths based on
-    // `config.project_root`
-    env::set_current_dir(&root).map_err(|src| Error::ChangingDir {
-        cwd: cwd.display().to_string(),
-        root: root.display().to_string(),
+pub(crate) fn load(location: &Location) -> Result<Config> {
+    let (root, path) = location.resolve()?;
+
+    debug!(
+        "using project root `{}` with `{}`",
+        root.display(),
+        path.display()
+    );
+
+    let content = fs::read_to_string(&path).map_err(|src| Error::Reading {
+        path: path.display().to_string(),
         src,
     })?;
 
-    let raw: Raw = parse(content.as_str())?;
+    let raw: Raw = parse(&path, content.as_str())?;
 
     Ok(Config {
         strip_directives: raw.strip_directives,
@@ -255,18 +357,31 @@ pub(crate) fn load() -> Result<Config> {
                 .map(|s| expand_and_resolve(s, &root))
                 .transpose()?,
             root: root.clone(),
+            config_file: path,
         },
         dirs: ResolvedDirs {
             themes: expand_and_resolve(&raw.dirs.themes, &root)?,
             schemes: expand_and_resolve(&raw.dirs.schemes, &root)?,
             templates: expand_and_resolve(&raw.dirs.templates, &root)?,
         },
+        library: raw.library,
+        install: ResolvedInstall {
+            mode: raw.install.mode,
+            paths: raw
+                .install
+                .paths
+                .iter()
+                .map(|(glob, dir)| {
+                    Ok((glob.clone(), expand_and_resolve(dir, &root)?))
+                })
+                .collect::<Result<_>>()?,
+        },
         providers: merge_providers_with_defaults(&raw.providers),
     })
 }
 
 
-pub(crate) fn parse<T>(content: &str) -> Result<T>
+pub(crate) fn parse<T>(path: &Path, content: &str) -> Result<T>
 where
     T: serde::de::DeserializeOwned + Default,
 {
@@ -274,7 +389,13 @@ where
         return Ok(T::default());
     }
 
-    toml::from_str(content).map_err(|src| Error::Parsing { src: Box::new(src) })
+    toml::from_str(content).map_err(|src| Error::Parsing {
+        path: path.display().to_string(),
+        diagnostic: Box::new(
+            Source::new(path, content).diagnose(src.span(), None),
+        ),
+        src: Box::new(src),
+    })
 }
 
 
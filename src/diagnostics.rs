@@ -0,0 +1,271 @@
+use std::fmt::{Display, Formatter, Result as FmtResult};
+use std::ops::Range;
+use std::path::Path;
+
+use toml::Spanned;
+use toml::de::{DeString, DeTable, DeValue};
+
+
+/// A file's content alongside the spans of its keys and values.
+#[derive(Debug)]
+pub(crate) struct Source<'a> {
+    path: &'a Path,
+    content: &'a str,
+    table: Option<DeTable<'a>>,
+}
+
+impl<'a> Source<'a> {
+    pub(crate) fn new(path: &'a Path, content: &'a str) -> Self {
+        Self {
+            path,
+            content,
+            table: DeTable::parse(content).ok().map(Spanned::into_inner),
+        }
+    }
+
+    /// The span of the last key in `keys`, e.g. `["roles", "ansi", "red"]`.
+    pub(crate) fn key(&self, keys: &[&str]) -> Option<Range<usize>> {
+        self.entry(keys).map(|(key, _)| key.span())
+    }
+
+    /// The span of the value at `keys`.
+    pub(crate) fn value(&self, keys: &[&str]) -> Option<Range<usize>> {
+        self.entry(keys).map(|(_, value)| value.span())
+    }
+
+    pub(crate) fn locate(&self, span: Range<usize>) -> Location {
+        Location::new(self.path.display().to_string(), self.content, span)
+    }
+
+    pub(crate) fn diagnose(
+        &self,
+        span: Option<Range<usize>>,
+        help: Option<String>,
+    ) -> Diagnostic {
+        Diagnostic {
+            location: span.map(|span| self.locate(span)),
+            help,
+        }
+    }
+
+    /// Points `error` at `span`, with an optional hint on how to fix it.
+    pub(crate) fn error<E>(
+        &self,
+        error: E,
+        span: Option<Range<usize>>,
+        help: Option<String>,
+    ) -> crate::Error
+    where
+        E: Into<crate::Error>,
+    {
+        self.diagnose(span, help).attach(error)
+    }
+
+    fn entry(
+        &self,
+        keys: &[&str],
+    ) -> Option<(&Spanned<DeString<'a>>, &Spanned<DeValue<'a>>)> {
+        let (last, parents) = keys.split_last()?;
+        let mut table = self.table.as_ref()?;
+
+        for key in parents {
+            table = table.get(*key)?.get_ref().as_table()?;
+        }
+
+        table.get_key_value(*last)
+    }
+}
+
+
+/// Where an error happened and how it might be fixed.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct Diagnostic {
+    pub location: Option<Location>,
+    pub help: Option<String>,
+}
+
+impl Diagnostic {
+    #[must_use]
+    pub(crate) const fn is_empty(&self) -> bool {
+        self.location.is_none() && self.help.is_none()
+    }
+
+    pub(crate) fn attach<E>(self, error: E) -> crate::Error
+    where
+        E: Into<crate::Error>,
+    {
+        let error = error.into();
+
+        if self.is_empty() {
+            return error;
+        }
+
+        crate::Error::Diagnosed {
+            error: Box::new(error),
+            diagnostic: self,
+        }
+    }
+}
+
+impl Display for Diagnostic {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        let gutter = self
+            .location
+            .as_ref()
+            .map_or(1, |location| location.line.to_string().len());
+
+        if let Some(location) = &self.location {
+            write!(f, "\n{location}")?;
+        }
+
+        if let Some(help) = &self.help {
+            write!(f, "\n{:gutter$} = help: {help}", "")?;
+        }
+
+        Ok(())
+    }
+}
+
+/// A `file:line:column` position with the offending line underlined.
+#[derive(Debug, Clone)]
+pub(crate) struct Location {
+    path: String,
+    line: usize,
+    column: usize,
+    text: String,
+    width: usize,
+}
+
+impl Location {
+    /// Locates the byte range `span` of `content`, read from `path`.
+    pub(crate) fn new(path: String, content: &str, span: Range<usize>) -> Self {
+        let start = span.start.min(content.len());
+        let before = content.get(..start).unwrap_or_default();
+        let line_start = before.rfind('\n').map_or(0, |i| i + 1);
+        let line_end = content
+            .get(start..)
+            .and_then(|rest| rest.find('\n'))
+            .map_or(content.len(), |i| start + i);
+        let end = span.end.clamp(start, line_end);
+
+        Self {
+            path,
+            line: before.matches('\n').count() + 1,
+            column: before.get(line_start..).map_or(0, |s| s.chars().count())
+                + 1,
+            text: content
+                .get(line_start..line_end)
+                .unwrap_or_default()
+                .trim_end_matches('\r')
+                .to_owned(),
+            width: content
+                .get(start..end)
+                .map_or(0, |s| s.chars().count())
+                .max(1),
+        }
+    }
+}
+
+impl Display for Location {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        let line = self.line.to_string();
+        let gutter = line.len();
+
+        writeln!(
+            f,
+            "{:gutter$}--> {}:{}:{}",
+            "", self.path, self.line, self.column
+        )?;
+        writeln!(f, "{:gutter$} |", "")?;
+        writeln!(f, "{line} | {}", self.text)?;
+        write!(
+            f,
+            "{:gutter$} | {:indent$}{}",
+            "",
+            "",
+            "^".repeat(self.width),
+            indent = self.column.saturating_sub(1)
+        )
+    }
+}
+
+
+/// The candidate closest to `name`, if any is close enough to likely be what
+/// was meant.
+pub(crate) fn did_you_mean<'c, I>(name: &str, candidates: I) -> Option<&'c str>
+where
+    I: IntoIterator<Item = &'c str>,
+{
+    let name = name.to_lowercase();
+    let max = name.chars().count().div_ceil(3).max(1);
+
+    candidates
+        .into_iter()
+        .map(|candidate| {
+            (distance(&name, &candidate.to_lowercase()), candidate)
+        })
+        .filter(|&(score, _)| score <= max)
+        .min_by_key(|&(score, _)| score)
+        .map(|(_, candidate)| candidate)
+}
+
+/// Levenshtein distance between `a` and `b`.
+fn distance(a: &str, b: &str) -> usize {
+    let mut previous: Vec<usize> = (0..=b.chars().count()).collect();
+
+    for (i, a) in a.chars().enumerate() {
+        let mut current = vec![i + 1];
+
+        for ((b, diagonal), above) in
+            b.chars().zip(&previous).zip(previous.iter().skip(1))
+        {
+            let left = current.last().copied().unwrap_or_default();
+
+            current.push(
+                (diagonal + usize::from(a != b))
+                    .min(above + 1)
+                    .min(left + 1),
+            );
+        }
+
+        previous = current;
+    }
+
+    previous.last().copied().unwrap_or_default()
+}
+
+
+#[cfg(test)]
+mod tests {
+    use indoc::indoc;
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+
+    #[test]
+    fn underlines_value() {
+        let content = indoc! {r#"
+            [roles.ansi]
+            black = "ansi.blakc"
+        "#};
+        let source = Source::new(Path::new("dark.toml"), content);
+        let span = source
+            .value(&["roles", "ansi", "black"])
+            .unwrap_or_else(|| panic!("value should have a span"));
+
+        assert_eq!(source.locate(span).to_string(), indoc! {r#"
+                 --> dark.toml:2:9
+                  |
+                2 | black = "ansi.blakc"
+                  |         ^^^^^^^^^^^^"#});
+    }
+
+    #[test]
+    fn suggests_close_names() {
+        let roles = ["ansi.black", "ansi.blue", "ui.background"];
+
+        assert_eq!(did_you_mean("ansi.blakc", roles), Some("ansi.black"));
+        assert_eq!(did_you_mean("ui.backround", roles), Some("ui.background"));
+        assert_eq!(did_you_mean("syntax.keyword", roles), None);
+    }
+}
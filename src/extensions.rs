@@ -1,7 +1,10 @@
 use std::path::Path;
 
+use indexmap::IndexMap;
+
 use crate::config::Provider;
-use crate::themes::{Extra, Meta, Palette, RawScheme, Roles};
+use crate::diagnostics::Location;
+use crate::themes::{Extra, Meta, Palette, RawScheme, RoleName, Roles};
 
 
 pub(crate) trait Merge: Sized {
@@ -69,12 +72,21 @@ impl Merge for Roles {
     }
 }
 
+impl Merge for IndexMap<RoleName, Location> {
+    fn merge(self, mut base: Self) -> Self {
+        base.extend(self);
+
+        base
+    }
+}
+
 impl_merge_for_all_fields!(RawScheme {
     name_ascii,
     meta,
     palette,
     roles,
     extra,
+    role_locations,
 });
 
 impl Merge for Provider {
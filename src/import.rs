@@ -0,0 +1,577 @@
+use std::ffi::OsStr;
+use std::path::Path;
+use std::result::Result as StdResult;
+use std::{fs, io};
+
+use hex_color::HexColor;
+use indexmap::IndexMap;
+use log::{info, warn};
+
+use crate::atomic;
+use crate::output::events;
+use crate::themes::{RoleName, all_roles, base_roles, schemes};
+
+
+mod alacritty;
+mod base16;
+mod ghostty;
+mod helix;
+mod iterm2;
+mod kitty;
+mod windows_terminal;
+
+
+/// Swatch names and roles for the 16 ansi colors, in terminal order.
+const ANSI: [(&str, &str); 16] = [
+    ("black", "ansi.black"),
+    ("red", "ansi.red"),
+    ("green", "ansi.green"),
+    ("yellow", "ansi.yellow"),
+    ("blue", "ansi.blue"),
+    ("magenta", "ansi.magenta"),
+    ("cyan", "ansi.cyan"),
+    ("white", "ansi.white"),
+    ("bright_black", "ansi.black_bright"),
+    ("bright_red", "ansi.red_bright"),
+    ("bright_green", "ansi.green_bright"),
+    ("bright_yellow", "ansi.yellow_bright"),
+    ("bright_blue", "ansi.blue_bright"),
+    ("bright_magenta", "ansi.magenta_bright"),
+    ("bright_cyan", "ansi.cyan_bright"),
+    ("bright_white", "ansi.white_bright"),
+];
+
+/// Roles to reference, in order of preference, for required roles the source
+/// format has no equivalent for.
+const FALLBACKS: &[(&str, &[&str])] = &[
+    ("bg", &["ansi.black"]),
+    ("fg", &["ansi.white", "ansi.white_bright"]),
+    ("toolbar", &["bg_alt", "bg"]),
+    ("select", &["ansi.black_bright", "bg_alt", "ansi.black"]),
+    ("accent", &["ansi.magenta", "syntax.keyword"]),
+    ("inactive", &["syntax.comment", "ansi.black_bright", "fg"]),
+    ("focus", &["accent"]),
+    ("guide", &["inactive"]),
+    ("match", &["ansi.yellow", "warning"]),
+    ("error", &["ansi.red"]),
+    ("warning", &["ansi.yellow"]),
+    ("info", &["ansi.blue"]),
+    ("hint", &["ansi.cyan"]),
+    ("debug.active", &["warning"]),
+    ("debug.breakpoint", &["error"]),
+    ("debug.frameline", &["select"]),
+    ("mode.normal", &["accent"]),
+    ("mode.insert", &["ansi.green", "syntax.string"]),
+    ("mode.select", &["ansi.magenta", "syntax.keyword"]),
+    ("syntax.variable", &["fg"]),
+    ("syntax.keyword", &["ansi.magenta"]),
+    ("syntax.type", &["ansi.yellow"]),
+    ("syntax.function", &["ansi.blue"]),
+    ("syntax.constant", &["ansi.cyan"]),
+    ("syntax.label", &["ansi.cyan"]),
+    ("syntax.constructor", &["syntax.type"]),
+    ("syntax.string", &["ansi.green"]),
+    ("syntax.attribute", &["syntax.type"]),
+    ("syntax.namespace", &["syntax.type"]),
+    ("syntax.tag", &["ansi.red"]),
+    ("syntax.comment", &["ansi.black_bright", "inactive"]),
+    ("syntax.operator", &["fg"]),
+    ("syntax.punctuation", &["fg"]),
+    ("syntax.special", &["ansi.cyan"]),
+    ("diff.plus", &["ansi.green", "syntax.string"]),
+    ("diff.minus", &["ansi.red", "error"]),
+    ("diff.delta", &["ansi.yellow", "warning"]),
+    ("markup.heading", &["ansi.blue", "syntax.function"]),
+    ("markup.list", &["ansi.red", "syntax.tag"]),
+    ("markup.link", &["ansi.blue", "syntax.function"]),
+    ("markup.bold", &["fg"]),
+    ("markup.italic", &["fg"]),
+    ("markup.strikethrough", &["fg"]),
+    ("markup.quote", &["syntax.comment"]),
+    ("markup.raw", &["syntax.string"]),
+    ("ansi.black", &["bg"]),
+    ("ansi.red", &["error", "fg"]),
+    ("ansi.green", &["syntax.string", "fg"]),
+    ("ansi.yellow", &["warning", "fg"]),
+    ("ansi.blue", &["info", "fg"]),
+    ("ansi.magenta", &["syntax.keyword", "fg"]),
+    ("ansi.cyan", &["hint", "fg"]),
+    ("ansi.white", &["fg"]),
+];
+
+/// Values formats accept in place of a color, like kitty's `none` or
+/// alacritty's `CellForeground`, that have no swatch to import.
+const NOT_COLORS: &[&str] = &["none", "cellforeground", "cellbackground"];
+
+
+type Result<T> = StdResult<T, Error>;
+
+#[non_exhaustive]
+#[derive(Debug, thiserror::Error)]
+pub(crate) enum Error {
+    #[error("failed to read `{path}`: {src}")]
+    Reading { path: String, src: io::Error },
+
+    #[error("failed to write `{path}`: {src}")]
+    Writing { path: String, src: io::Error },
+
+    #[error("`{0}` already exists (use `--force` to overwrite)")]
+    Exists(String),
+
+    #[error("failed to detect the format of `{0}` (use `--format`)")]
+    UnknownFormat(String),
+
+    #[error("failed to parse {format} file: {reason}")]
+    Parsing { format: Format, reason: String },
+
+    #[error("invalid color `{value}` for `{key}`")]
+    InvalidColor { key: String, value: String },
+
+    #[error("no colors found to derive roles {0}")]
+    MissingRoles(String),
+
+    #[error("failed to serialize scheme: {0}")]
+    Serializing(#[from] toml::ser::Error),
+}
+
+
+#[non_exhaustive]
+#[derive(
+    Debug, Clone, Copy, PartialEq, Eq, strum::Display, clap::ValueEnum,
+)]
+#[strum(serialize_all = "kebab-case")]
+pub(crate) enum Format {
+    Base16,
+    Kitty,
+    Iterm2,
+    Ghostty,
+    Helix,
+    Alacritty,
+    WindowsTerminal,
+}
+
+impl Format {
+    fn detect(path: &Path, content: &str) -> Option<Self> {
+        let ext = path.extension().and_then(|e| e.to_str()).unwrap_or("");
+
+        match ext.to_lowercase().as_str() {
+            "yaml" | "yml" => Some(Self::Base16),
+            "itermcolors" => Some(Self::Iterm2),
+            "json" => Some(Self::WindowsTerminal),
+            "conf" => Some(Self::Kitty),
+            "toml" => Some(
+                if content.lines().any(|l| l.trim().starts_with("[colors")) {
+                    Self::Alacritty
+                } else {
+                    Self::Helix
+                },
+            ),
+            "" => Some(
+                if content.lines().any(|l| l.trim().starts_with("palette")) {
+                    Self::Ghostty
+                } else {
+                    Self::Kitty
+                },
+            ),
+            _ => None,
+        }
+    }
+
+    fn parse(self, content: &str, scheme: &mut Scheme) -> Result<()> {
+        match self {
+            Self::Base16 => base16::parse(content, scheme),
+            Self::Kitty => kitty::parse(content, scheme),
+            Self::Iterm2 => iterm2::parse(content, scheme),
+            Self::Ghostty => ghostty::parse(content, scheme),
+            Self::Helix => helix::parse(content, scheme),
+            Self::Alacritty => alacritty::parse(content, scheme),
+            Self::WindowsTerminal => windows_terminal::parse(content, scheme),
+        }
+    }
+}
+
+
+#[derive(Debug, Clone)]
+enum Value {
+    Swatch(String),
+    Role(RoleName),
+}
+
+/// A scheme being assembled from an imported file. Colors are deduped by hex
+/// value, so the first name given to a color sticks.
+#[derive(Debug, Default)]
+struct Scheme {
+    name: Option<String>,
+    author: Option<String>,
+    palette: IndexMap<String, String>,
+    roles: IndexMap<RoleName, Value>,
+}
+
+impl Scheme {
+    /// Adds `value` to the palette as `name` and assigns it to each of
+    /// `roles` that isn't set yet.
+    fn color(&mut self, name: &str, value: &str, roles: &[&str]) -> Result<()> {
+        if NOT_COLORS.contains(&value.to_lowercase().as_str()) {
+            warn!("skipped `{name}`, `{value}` isn't a color");
+
+            return Ok(());
+        }
+
+        let hex = normalize_hex(value).ok_or_else(|| Error::InvalidColor {
+            key: name.to_owned(),
+            value: value.to_owned(),
+        })?;
+
+        let swatch = if let Some(swatch) = self.palette.get(&hex) {
+            swatch.clone()
+        } else {
+            let swatch = self.unique_name(&sanitize(name));
+
+            self.palette.insert(hex, swatch.clone());
+
+            swatch
+        };
+
+        for role in roles {
+            if let Ok(role) = role.parse() {
+                self.roles
+                    .entry(role)
+                    .or_insert_with(|| Value::Swatch(swatch.clone()));
+            }
+        }
+
+        Ok(())
+    }
+
+    fn is_set(&self, role: &str) -> bool {
+        role.parse::<RoleName>()
+            .is_ok_and(|role| self.roles.contains_key(&role))
+    }
+
+    fn unique_name(&self, name: &str) -> String {
+        let taken =
+            |candidate: &str| self.palette.values().any(|n| n == candidate);
+
+        if !taken(name) {
+            return name.to_owned();
+        }
+
+        // there are fewer taken names than candidates, so one is always free
+        (2..=self.palette.len() + 2)
+            .map(|i| format!("{name}_{i}"))
+            .find(|candidate| !taken(candidate))
+            .unwrap_or_else(|| name.to_owned())
+    }
+
+    /// Points every required role the source didn't cover at the closest
+    /// role it did.
+    fn fill_fallbacks(&mut self) -> Result<()> {
+        loop {
+            let mut progress = false;
+
+            for role in base_roles() {
+                if self.roles.contains_key(&role) {
+                    continue;
+                }
+
+                let candidate = FALLBACKS
+                    .iter()
+                    .find(|(name, _)| *name == role.as_str())
+                    .and_then(|(_, candidates)| {
+                        candidates.iter().find(|c| self.is_set(c))
+                    })
+                    .and_then(|c| c.parse().ok());
+
+                if let Some(target) = candidate {
+                    self.roles.insert(role, Value::Role(target));
+
+                    progress = true;
+                }
+            }
+
+            if !progress {
+                break;
+            }
+        }
+
+        let missing: Vec<String> = base_roles()
+            .filter(|role| !self.roles.contains_key(role))
+            .map(|role| format!("`{role}`"))
+            .collect();
+
+        if missing.is_empty() {
+            Ok(())
+        } else {
+            Err(Error::MissingRoles(missing.join(", ")))
+        }
+    }
+
+    fn to_toml(&self) -> Result<String> {
+        let mut meta = toml::Table::new();
+
+        if let Some(author) = &self.author {
+            meta.insert("author".to_owned(), author.clone().into());
+        }
+
+        let palette: toml::Table = self
+            .palette
+            .iter()
+            .map(|(hex, name)| (name.clone(), hex.clone().into()))
+            .collect();
+
+        let mut roles_table = toml::Table::new();
+        let mut groups: IndexMap<&str, toml::Table> = IndexMap::new();
+
+        for role in all_roles() {
+            let Some(value) = self.roles.get(&role) else {
+                continue;
+            };
+            let value = match value {
+                Value::Swatch(name) => format!("${name}"),
+                Value::Role(name) => name.to_string(),
+            };
+
+            if let Some((group, key)) = role.as_str().split_once('.') {
+                groups
+                    .entry(group)
+                    .or_default()
+                    .insert(key.to_owned(), value.into());
+            } else {
+                roles_table.insert(role.to_string(), value.into());
+            }
+        }
+
+        for (group, table) in groups {
+            roles_table.insert(group.to_owned(), table.into());
+        }
+
+        let mut doc = toml::Table::new();
+
+        if !meta.is_empty() {
+            doc.insert("meta".to_owned(), meta.into());
+        }
+
+        doc.insert("palette".to_owned(), palette.into());
+        doc.insert("roles".to_owned(), roles_table.into());
+
+        Ok(toml::to_string(&doc)?)
+    }
+}
+
+
+/// Converts `path` into a scheme and writes it to `output`, or prints it
+/// when no output is given. Existing files are only replaced with `force`.
+pub(crate) fn run(
+    path: &Path,
+    format: Option<Format>,
+    output: Option<&Path>,
+    force: bool,
+) -> crate::Result<()> {
+    let content = fs::read_to_string(path).map_err(|src| Error::Reading {
+        path: path.display().to_string(),
+        src,
+    })?;
+
+    let format = format
+        .or_else(|| Format::detect(path, &content))
+        .ok_or_else(|| Error::UnknownFormat(path.display().to_string()))?;
+
+    let mut scheme = Scheme::default();
+
+    format.parse(&content, &mut scheme)?;
+    scheme.fill_fallbacks()?;
+
+    let toml = scheme.to_toml()?;
+    let stem = path.file_stem().and_then(OsStr::to_str);
+    let name = sanitize(scheme.name.as_deref().or(stem).unwrap_or("imported"));
+
+    let Some(output) = output else {
+        schemes::parse(&name, path, &toml)?;
+        events::print(toml.trim_end());
+
+        return Ok(());
+    };
+
+    let output = if output.is_dir() {
+        output.join(format!("{name}.toml"))
+    } else {
+        output.to_path_buf()
+    };
+
+    // make sure the result actually loads as a scheme before it's written
+    schemes::parse(&name, &output, &toml)?;
+
+    if !force && output.exists() {
+        return Err(Error::Exists(output.display().to_string()).into());
+    }
+
+    atomic::write(&output, &toml).map_err(|src| Error::Writing {
+        path: output.display().to_string(),
+        src,
+    })?;
+
+    info!(
+        "imported {format} scheme `{}` into `{}` ({} swatches)",
+        path.display(),
+        output.display(),
+        scheme.palette.len()
+    );
+
+    Ok(())
+}
+
+
+fn normalize_hex(value: &str) -> Option<String> {
+    let value = value.trim().trim_matches(['"', '\'']);
+    let digits = value
+        .strip_prefix('#')
+        .or_else(|| value.strip_prefix("0x"))
+        .unwrap_or(value);
+
+    let color = HexColor::parse(&format!("#{digits}")).ok()?;
+
+    Some(format!("#{:02x}{:02x}{:02x}", color.r, color.g, color.b))
+}
+
+fn sanitize(name: &str) -> String {
+    let sanitized: String = name
+        .trim()
+        .to_lowercase()
+        .chars()
+        .map(|c| {
+            if c.is_alphanumeric() || c == '-' {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect();
+    let sanitized = sanitized.trim_matches('_');
+
+    if sanitized.is_empty() {
+        "color".to_owned()
+    } else {
+        sanitized.to_owned()
+    }
+}
+
+/// Splits a `key = value`/`key: value`/`key value` line, ignoring blank
+/// lines and `#` comments.
+fn split_line(line: &str, separator: Option<char>) -> Option<(&str, &str)> {
+    let line = line.trim();
+
+    if line.is_empty() || line.starts_with('#') {
+        return None;
+    }
+
+    let (key, value) = match separator {
+        Some(sep) => line.split_once(sep)?,
+        None => line.split_once(char::is_whitespace)?,
+    };
+
+    Some((key.trim(), value.trim().trim_matches(['"', '\''])))
+}
+
+const fn parsing(format: Format, reason: String) -> Error {
+    Error::Parsing { format, reason }
+}
+
+#[cfg(test)]
+mod tests {
+    use indoc::indoc;
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+
+    fn import(format: Format, content: &str) -> Scheme {
+        let mut scheme = Scheme::default();
+
+        format
+            .parse(content, &mut scheme)
+            .unwrap_or_else(|e| panic!("failed to parse {format}: {e}"));
+        scheme
+            .fill_fallbacks()
+            .unwrap_or_else(|e| panic!("failed to fill fallbacks: {e}"));
+
+        scheme
+    }
+
+    #[test]
+    fn identical_colors_share_a_swatch() {
+        let scheme = import(Format::Kitty, indoc! {"
+                background #181716
+                foreground #e0dfdc
+                color0     #181716
+                color7     #E0DFDC
+            "});
+
+        assert_eq!(scheme.palette.len(), 2, "duplicate hex values kept");
+        assert_eq!(
+            scheme.palette.get("#181716").map(String::as_str),
+            Some("background"),
+            "first name given to a color should stick"
+        );
+    }
+
+    #[test]
+    fn skips_values_that_arent_colors() {
+        let kitty = import(Format::Kitty, indoc! {"
+                background           #181716
+                foreground           #e0dfdc
+                cursor               none
+                selection_background none
+            "});
+        let alacritty = import(Format::Alacritty, indoc! {r##"
+                [colors.primary]
+                background = "#181716"
+                foreground = "#e0dfdc"
+
+                [colors.cursor]
+                text = "CellBackground"
+                cursor = "CellForeground"
+            "##});
+
+        let focus: RoleName = "focus".parse().expect("`focus` is a role");
+
+        for scheme in [kitty, alacritty] {
+            assert_eq!(scheme.palette.len(), 2, "non-colors kept as swatches");
+            assert!(
+                matches!(scheme.roles.get(&focus), Some(Value::Role(_))),
+                "`focus` should fall back instead of using a non-color"
+            );
+        }
+    }
+
+    #[test]
+    fn imported_scheme_loads() {
+        let scheme = import(Format::Base16, indoc! {r#"
+                scheme: "Cutie Test"
+                author: "someone"
+                base00: "181716"
+                base01: "201f1e"
+                base02: "2e2c2a"
+                base03: "5c5855"
+                base04: "8a8580"
+                base05: "e0dfdc"
+                base06: "f0efec"
+                base07: "ffffff"
+                base08: "ff6188"
+                base09: "fc9867"
+                base0A: "ffd866"
+                base0B: "a9dc76"
+                base0C: "78dce8"
+                base0D: "6796e6"
+                base0E: "ab9df2"
+                base0F: "e07a5f"
+            "#});
+        let toml = scheme
+            .to_toml()
+            .unwrap_or_else(|e| panic!("failed to serialize: {e}"));
+
+        if let Err(e) =
+            schemes::parse("cutie-test", Path::new("cutie-test.toml"), &toml)
+        {
+            panic!("imported scheme failed to load: {e}\n\n{toml}");
+        }
+    }
+}
@@ -0,0 +1,43 @@
+use super::{ANSI, Format, Result, Scheme, parsing};
+
+const KEYS: &[(&str, &str, &[&str])] = &[
+    ("primary", "background", &["bg"]),
+    ("primary", "foreground", &["fg"]),
+    ("selection", "background", &["select"]),
+    ("cursor", "cursor", &["focus"]),
+];
+
+pub(super) fn parse(content: &str, scheme: &mut Scheme) -> Result<()> {
+    let root: toml::Table = toml::from_str(content)
+        .map_err(|e| parsing(Format::Alacritty, e.to_string()))?;
+    let colors = root
+        .get("colors")
+        .and_then(toml::Value::as_table)
+        .ok_or_else(|| {
+            parsing(Format::Alacritty, "missing `[colors]` table".to_owned())
+        })?;
+
+    let color = |section: &str, key: &str| {
+        colors
+            .get(section)
+            .and_then(|s| s.get(key))
+            .and_then(toml::Value::as_str)
+    };
+
+    for (section, key, roles) in KEYS {
+        if let Some(value) = color(section, key) {
+            scheme.color(&format!("{section}_{key}"), value, roles)?;
+        }
+    }
+
+    for (i, (name, role)) in ANSI.iter().enumerate() {
+        let section = if i < 8 { "normal" } else { "bright" };
+        let key = name.trim_start_matches("bright_");
+
+        if let Some(value) = color(section, key) {
+            scheme.color(name, value, &[role])?;
+        }
+    }
+
+    Ok(())
+}
@@ -0,0 +1,98 @@
+use super::{Format, Result, Scheme, parsing, split_line};
+
+/// Roles per base16/base24 slot, following the tinted-theming styling
+/// guidelines.
+const SLOTS: &[(&str, &[&str])] = &[
+    ("base00", &["bg", "ansi.black"]),
+    ("base01", &["bg_alt", "toolbar"]),
+    ("base02", &["select"]),
+    ("base03", &[
+        "syntax.comment",
+        "inactive",
+        "ansi.black_bright",
+    ]),
+    ("base04", &["fg_alt", "guide"]),
+    ("base05", &["fg", "ansi.white"]),
+    ("base06", &[]),
+    ("base07", &["ansi.white_bright"]),
+    ("base08", &[
+        "syntax.variable",
+        "syntax.tag",
+        "markup.list",
+        "diff.minus",
+        "error",
+        "ansi.red",
+    ]),
+    ("base09", &["syntax.constant", "markup.link"]),
+    ("base0A", &[
+        "syntax.type",
+        "markup.bold",
+        "warning",
+        "ansi.yellow",
+    ]),
+    ("base0B", &[
+        "syntax.string",
+        "markup.raw",
+        "diff.plus",
+        "ansi.green",
+    ]),
+    ("base0C", &[
+        "syntax.special",
+        "markup.quote",
+        "hint",
+        "ansi.cyan",
+    ]),
+    ("base0D", &[
+        "syntax.function",
+        "markup.heading",
+        "info",
+        "ansi.blue",
+    ]),
+    ("base0E", &[
+        "syntax.keyword",
+        "markup.italic",
+        "diff.delta",
+        "accent",
+        "ansi.magenta",
+    ]),
+    ("base0F", &["syntax.special_punctuation"]),
+    ("base10", &[]),
+    ("base11", &[]),
+    ("base12", &["ansi.red_bright"]),
+    ("base13", &["ansi.yellow_bright"]),
+    ("base14", &["ansi.green_bright"]),
+    ("base15", &["ansi.cyan_bright"]),
+    ("base16", &["ansi.blue_bright"]),
+    ("base17", &["ansi.magenta_bright"]),
+];
+
+pub(super) fn parse(content: &str, scheme: &mut Scheme) -> Result<()> {
+    let mut found = false;
+
+    for (key, value) in content.lines().filter_map(|l| split_line(l, Some(':')))
+    {
+        match key {
+            "scheme" | "name" => scheme.name = Some(value.to_owned()),
+            "author" => scheme.author = Some(value.to_owned()),
+            _ => {
+                if let Some((slot, roles)) = SLOTS
+                    .iter()
+                    .find(|(slot, _)| slot.eq_ignore_ascii_case(key))
+                {
+                    scheme.color(slot, value, roles)?;
+
+                    found = true;
+                }
+            }
+        }
+    }
+
+    if found {
+        Ok(())
+    } else {
+        Err(parsing(
+            Format::Base16,
+            "no `baseXX` colors found".to_owned(),
+        ))
+    }
+}
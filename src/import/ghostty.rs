@@ -0,0 +1,43 @@
+use super::{ANSI, Format, Result, Scheme, parsing, split_line};
+
+const KEYS: &[(&str, &[&str])] = &[
+    ("background", &["bg"]),
+    ("foreground", &["fg"]),
+    ("selection-background", &["select"]),
+    ("cursor-color", &["focus"]),
+];
+
+pub(super) fn parse(content: &str, scheme: &mut Scheme) -> Result<()> {
+    let mut found = false;
+
+    for (key, value) in content.lines().filter_map(|l| split_line(l, Some('=')))
+    {
+        if key == "palette" {
+            let (index, color) = value.split_once('=').ok_or_else(|| {
+                parsing(
+                    Format::Ghostty,
+                    format!("expected `palette = N=#rrggbb`, found `{value}`"),
+                )
+            })?;
+
+            if let Ok(index) = index.trim().parse::<usize>()
+                && let Some((name, role)) = ANSI.get(index)
+            {
+                scheme.color(name, color, &[role])?;
+
+                found = true;
+            }
+        } else if let Some((name, roles)) = KEYS.iter().find(|(k, _)| *k == key)
+        {
+            scheme.color(name, value, roles)?;
+
+            found = true;
+        }
+    }
+
+    if found {
+        Ok(())
+    } else {
+        Err(parsing(Format::Ghostty, "no colors found".to_owned()))
+    }
+}
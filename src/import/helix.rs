@@ -0,0 +1,164 @@
+use log::debug;
+use toml::Value as Toml;
+
+use super::{Format, Result, Scheme, parsing};
+
+/// Helix scopes, the style attribute to read and the roles it maps to.
+/// Optional roles only map to their exact scope, so anything Helix would
+/// inherit from a parent scope falls back to the base role instead.
+const SCOPES: &[(&str, &str, &[&str])] = &[
+    ("ui.background", "bg", &["bg"]),
+    ("ui.text", "fg", &["fg"]),
+    ("ui.text.inactive", "fg", &["fg_alt"]),
+    ("ui.statusline", "bg", &["toolbar"]),
+    ("ui.statusline.inactive", "bg", &["toolbar_alt"]),
+    ("ui.popup", "bg", &["toolbar_popup"]),
+    ("ui.menu", "bg", &["toolbar_popup"]),
+    ("ui.selection", "bg", &["select"]),
+    ("ui.selection.primary", "bg", &["select_2nd"]),
+    ("ui.menu.selected", "bg", &["select_alt"]),
+    ("ui.cursor.primary", "bg", &["focus"]),
+    ("ui.cursor", "bg", &["focus"]),
+    ("ui.window", "fg", &["accent_separator"]),
+    ("ui.popup.info", "fg", &["accent_popup"]),
+    ("ui.linenr.selected", "fg", &["accent_linenum"]),
+    ("ui.linenr", "fg", &["guide_linenum"]),
+    ("ui.virtual.indent-guide", "fg", &["guide"]),
+    ("ui.virtual.inlay-hint", "fg", &["guide_inlay"]),
+    ("ui.virtual.ruler", "bg", &["guide_ruler"]),
+    ("ui.virtual.whitespace", "fg", &["guide_whitespace"]),
+    ("ui.cursor.match", "bg", &["match"]),
+    ("error", "fg", &["error"]),
+    ("warning", "fg", &["warning"]),
+    ("info", "fg", &["info"]),
+    ("hint", "fg", &["hint"]),
+    ("ui.debug.active", "fg", &["debug.active"]),
+    ("ui.debug.breakpoint", "fg", &["debug.breakpoint"]),
+    ("ui.highlight.frameline", "bg", &["debug.frameline"]),
+    ("ui.statusline.normal", "bg", &["mode.normal"]),
+    ("ui.statusline.insert", "bg", &["mode.insert"]),
+    ("ui.statusline.select", "bg", &["mode.select"]),
+    ("variable", "fg", &["syntax.variable"]),
+    ("variable.builtin", "fg", &["syntax.variable_builtin"]),
+    ("variable.parameter", "fg", &["syntax.variable_parameter"]),
+    ("variable.other.member", "fg", &["syntax.variable_member"]),
+    ("keyword", "fg", &["syntax.keyword", "accent"]),
+    ("keyword.operator", "fg", &["syntax.keyword_operator"]),
+    ("keyword.function", "fg", &["syntax.keyword_function"]),
+    ("keyword.control.conditional", "fg", &[
+        "syntax.keyword_conditional",
+    ]),
+    ("keyword.control.repeat", "fg", &["syntax.keyword_repeat"]),
+    ("keyword.control.import", "fg", &["syntax.keyword_import"]),
+    ("keyword.control.return", "fg", &["syntax.keyword_return"]),
+    ("keyword.control.exception", "fg", &[
+        "syntax.keyword_exception",
+    ]),
+    ("keyword.directive", "fg", &["syntax.keyword_directive"]),
+    ("keyword.storage", "fg", &["syntax.keyword_storage"]),
+    ("type", "fg", &["syntax.type"]),
+    ("type.builtin", "fg", &["syntax.type_builtin"]),
+    ("type.enum.variant", "fg", &["syntax.type_variant"]),
+    ("function", "fg", &["syntax.function"]),
+    ("function.builtin", "fg", &["syntax.function_builtin"]),
+    ("function.method", "fg", &["syntax.function_method"]),
+    ("function.macro", "fg", &["syntax.function_macro"]),
+    ("function.special", "fg", &["syntax.special_function"]),
+    ("constant", "fg", &["syntax.constant"]),
+    ("constant.builtin", "fg", &["syntax.constant_builtin"]),
+    ("constant.builtin.boolean", "fg", &[
+        "syntax.constant_boolean",
+    ]),
+    ("constant.numeric", "fg", &["syntax.constant_number"]),
+    ("constant.character", "fg", &["syntax.constant_character"]),
+    ("constant.character.escape", "fg", &[
+        "syntax.special_character",
+    ]),
+    ("label", "fg", &["syntax.label"]),
+    ("constructor", "fg", &["syntax.constructor"]),
+    ("string", "fg", &["syntax.string"]),
+    ("string.special", "fg", &[
+        "syntax.special",
+        "syntax.special_string",
+    ]),
+    ("attribute", "fg", &["syntax.attribute"]),
+    ("namespace", "fg", &["syntax.namespace"]),
+    ("tag", "fg", &["syntax.tag"]),
+    ("tag.builtin", "fg", &["syntax.tag_builtin"]),
+    ("comment", "fg", &["syntax.comment", "inactive"]),
+    ("comment.block.documentation", "fg", &["syntax.comment_doc"]),
+    ("operator", "fg", &["syntax.operator"]),
+    ("punctuation", "fg", &["syntax.punctuation"]),
+    ("punctuation.special", "fg", &["syntax.special_punctuation"]),
+    ("special", "fg", &["syntax.special"]),
+    ("diff.plus", "fg", &["diff.plus"]),
+    ("diff.minus", "fg", &["diff.minus"]),
+    ("diff.delta", "fg", &["diff.delta"]),
+    ("diff.delta.moved", "fg", &["diff.delta_moved"]),
+    ("diff.delta.conflict", "fg", &["diff.delta_conflict"]),
+    ("markup.heading", "fg", &["markup.heading"]),
+    ("markup.heading.1", "fg", &["markup.heading"]),
+    ("markup.heading.2", "fg", &["markup.heading_2nd"]),
+    ("markup.heading.3", "fg", &["markup.heading_3rd"]),
+    ("markup.heading.4", "fg", &["markup.heading_4th"]),
+    ("markup.heading.5", "fg", &["markup.heading_5th"]),
+    ("markup.heading.6", "fg", &["markup.heading_6th"]),
+    ("markup.list", "fg", &["markup.list"]),
+    ("markup.list.numbered", "fg", &["markup.list_numbered"]),
+    ("markup.list.checked", "fg", &["markup.list_checked"]),
+    ("markup.list.unchecked", "fg", &["markup.list_unchecked"]),
+    ("markup.link.url", "fg", &["markup.link"]),
+    ("markup.link.text", "fg", &["markup.link_text"]),
+    ("markup.bold", "fg", &["markup.bold"]),
+    ("markup.italic", "fg", &["markup.italic"]),
+    ("markup.strikethrough", "fg", &["markup.strikethrough"]),
+    ("markup.quote", "fg", &["markup.quote"]),
+    ("markup.raw", "fg", &["markup.raw"]),
+];
+
+/// Reads a Helix theme, resolving colors through its `[palette]`. Themes
+/// that `inherits` another theme only contribute the scopes they set.
+pub(super) fn parse(content: &str, scheme: &mut Scheme) -> Result<()> {
+    let root: toml::Table = toml::from_str(content)
+        .map_err(|e| parsing(Format::Helix, e.to_string()))?;
+    let palette = root.get("palette").and_then(Toml::as_table);
+
+    let mut found = false;
+
+    for (scope, attribute, roles) in SCOPES {
+        let value = match root.get(*scope) {
+            Some(Toml::String(fg)) if *attribute == "fg" => fg.as_str(),
+            Some(Toml::Table(style)) => {
+                let Some(value) = style.get(*attribute).and_then(Toml::as_str)
+                else {
+                    continue;
+                };
+
+                value
+            }
+            _ => continue,
+        };
+
+        if let Some(hex) =
+            palette.and_then(|p| p.get(value)).and_then(Toml::as_str)
+        {
+            scheme.color(value, hex, roles)?;
+        } else if value.starts_with('#') {
+            scheme.color(scope, value, roles)?;
+        } else {
+            // helix's named terminal colors (`red`, `light-gray`...) have no
+            // fixed value to import
+            debug!("skipping `{scope}`: `{value}` isn't a palette color");
+
+            continue;
+        }
+
+        found = true;
+    }
+
+    if found {
+        Ok(())
+    } else {
+        Err(parsing(Format::Helix, "no known scopes found".to_owned()))
+    }
+}
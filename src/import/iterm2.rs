@@ -0,0 +1,111 @@
+use quick_xml::Reader;
+use quick_xml::events::Event;
+
+use super::{ANSI, Format, Result, Scheme, parsing};
+
+const KEYS: &[(&str, &str, &[&str])] = &[
+    ("Background Color", "background", &["bg"]),
+    ("Foreground Color", "foreground", &["fg"]),
+    ("Selection Color", "selection", &["select"]),
+    ("Cursor Color", "cursor", &["focus"]),
+    ("Link Color", "link", &["markup.link"]),
+];
+
+const COMPONENTS: [&str; 3] =
+    ["Red Component", "Green Component", "Blue Component"];
+
+/// Reads the top-level `<dict>` of color entries, each of which is a nested
+/// `<dict>` of `0.0..=1.0` components. Color spaces are ignored and every
+/// color is treated as sRGB.
+pub(super) fn parse(content: &str, scheme: &mut Scheme) -> Result<()> {
+    let error = |e: &dyn ToString| parsing(Format::Iterm2, e.to_string());
+
+    let mut reader = Reader::from_str(content);
+    let mut depth = 0_usize;
+    let mut entry: Option<String> = None;
+    let mut component: Option<usize> = None;
+    let mut rgb = [0.0_f64; 3];
+    let mut found = false;
+
+    loop {
+        match reader.read_event().map_err(|e| error(&e))? {
+            Event::Eof => break,
+            Event::Start(tag) => match tag.name().as_ref() {
+                b"dict" => depth += 1,
+                b"key" => {
+                    let key =
+                        reader.read_text(tag.name()).map_err(|e| error(&e))?;
+
+                    if depth == 1 {
+                        entry = Some(key.trim().to_owned());
+                        rgb = [0.0; 3];
+                    } else {
+                        component =
+                            COMPONENTS.iter().position(|c| *c == key.trim());
+                    }
+                }
+                b"real" | b"integer" => {
+                    let value =
+                        reader.read_text(tag.name()).map_err(|e| error(&e))?;
+
+                    if depth == 2
+                        && let Some(slot) =
+                            component.take().and_then(|i| rgb.get_mut(i))
+                    {
+                        *slot = value.trim().parse().map_err(|e| error(&e))?;
+                    }
+                }
+                _ => {}
+            },
+            Event::End(tag) if tag.name().as_ref() == b"dict" => {
+                if depth == 2
+                    && let Some(key) = entry.take()
+                {
+                    found |= add(scheme, &key, rgb)?;
+                }
+
+                depth = depth.saturating_sub(1);
+            }
+            _ => {}
+        }
+    }
+
+    if found {
+        Ok(())
+    } else {
+        Err(parsing(Format::Iterm2, "no colors found".to_owned()))
+    }
+}
+
+fn add(scheme: &mut Scheme, key: &str, rgb: [f64; 3]) -> Result<bool> {
+    let [r, g, b] = rgb.map(to_byte);
+    let hex = format!("#{r:02x}{g:02x}{b:02x}");
+
+    if let Some(index) = key
+        .strip_prefix("Ansi ")
+        .and_then(|k| k.strip_suffix(" Color"))
+        .and_then(|i| i.parse::<usize>().ok())
+        && let Some((name, role)) = ANSI.get(index)
+    {
+        scheme.color(name, &hex, &[role])?;
+
+        return Ok(true);
+    }
+
+    if let Some((_, name, roles)) = KEYS.iter().find(|(k, ..)| *k == key) {
+        scheme.color(name, &hex, roles)?;
+
+        return Ok(true);
+    }
+
+    Ok(false)
+}
+
+#[expect(
+    clippy::cast_possible_truncation,
+    clippy::cast_sign_loss,
+    reason = "clamped to `0..=255` before casting"
+)]
+fn to_byte(component: f64) -> u8 {
+    (component.clamp(0.0, 1.0) * 255.0).round() as u8
+}
@@ -0,0 +1,39 @@
+use super::{ANSI, Format, Result, Scheme, parsing, split_line};
+
+const KEYS: &[(&str, &[&str])] = &[
+    ("background", &["bg"]),
+    ("foreground", &["fg"]),
+    ("selection_background", &["select"]),
+    ("cursor", &["focus"]),
+    ("url_color", &["markup.link"]),
+    ("active_border_color", &["accent"]),
+    ("inactive_border_color", &["inactive"]),
+    ("tab_bar_background", &["toolbar"]),
+    ("active_tab_background", &["toolbar_alt"]),
+];
+
+pub(super) fn parse(content: &str, scheme: &mut Scheme) -> Result<()> {
+    let mut found = false;
+
+    for (key, value) in content.lines().filter_map(|l| split_line(l, None)) {
+        if let Some(index) = key.strip_prefix("color")
+            && let Ok(index) = index.parse::<usize>()
+            && let Some((name, role)) = ANSI.get(index)
+        {
+            scheme.color(name, value, &[role])?;
+
+            found = true;
+        } else if let Some((name, roles)) = KEYS.iter().find(|(k, _)| *k == key)
+        {
+            scheme.color(name, value, roles)?;
+
+            found = true;
+        }
+    }
+
+    if found {
+        Ok(())
+    } else {
+        Err(parsing(Format::Kitty, "no colors found".to_owned()))
+    }
+}
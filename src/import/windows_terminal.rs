@@ -0,0 +1,54 @@
+use serde_json::Value as Json;
+
+use super::{Format, Result, Scheme, parsing};
+
+const KEYS: &[(&str, &str, &[&str])] = &[
+    ("background", "background", &["bg"]),
+    ("foreground", "foreground", &["fg"]),
+    ("selectionBackground", "selection", &["select"]),
+    ("cursorColor", "cursor", &["focus"]),
+    ("black", "black", &["ansi.black"]),
+    ("red", "red", &["ansi.red"]),
+    ("green", "green", &["ansi.green"]),
+    ("yellow", "yellow", &["ansi.yellow"]),
+    ("blue", "blue", &["ansi.blue"]),
+    ("purple", "magenta", &["ansi.magenta"]),
+    ("cyan", "cyan", &["ansi.cyan"]),
+    ("white", "white", &["ansi.white"]),
+    ("brightBlack", "bright_black", &["ansi.black_bright"]),
+    ("brightRed", "bright_red", &["ansi.red_bright"]),
+    ("brightGreen", "bright_green", &["ansi.green_bright"]),
+    ("brightYellow", "bright_yellow", &["ansi.yellow_bright"]),
+    ("brightBlue", "bright_blue", &["ansi.blue_bright"]),
+    ("brightPurple", "bright_magenta", &["ansi.magenta_bright"]),
+    ("brightCyan", "bright_cyan", &["ansi.cyan_bright"]),
+    ("brightWhite", "bright_white", &["ansi.white_bright"]),
+];
+
+/// Accepts a single scheme object or a `settings.json` with a `schemes`
+/// array, in which case the first scheme is imported.
+pub(super) fn parse(content: &str, scheme: &mut Scheme) -> Result<()> {
+    let root: Json = serde_json::from_str(content)
+        .map_err(|e| parsing(Format::WindowsTerminal, e.to_string()))?;
+    let object = root
+        .get("schemes")
+        .and_then(|schemes| schemes.get(0))
+        .unwrap_or(&root)
+        .as_object()
+        .ok_or_else(|| {
+            parsing(
+                Format::WindowsTerminal,
+                "expected a color scheme object".to_owned(),
+            )
+        })?;
+
+    scheme.name = object.get("name").and_then(Json::as_str).map(String::from);
+
+    for (key, name, roles) in KEYS {
+        if let Some(value) = object.get(*key).and_then(Json::as_str) {
+            scheme.color(name, value, roles)?;
+        }
+    }
+
+    Ok(())
+}
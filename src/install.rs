@@ -0,0 +1,410 @@
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+use std::result::Result as StdResult;
+use std::{fs, io};
+
+use globset::{Glob, GlobMatcher};
+use log::{debug, info, warn};
+use serde::{Deserialize, Serialize};
+
+use crate::config::{InstallMode as Mode, ResolvedInstall};
+use crate::render::Index;
+use crate::{Config, Manifest, ManifestEntry, manifest};
+
+
+type Result<T> = StdResult<T, Error>;
+
+#[non_exhaustive]
+#[derive(Debug, thiserror::Error)]
+pub(crate) enum Error {
+    #[error("failed to parse install glob `{pattern}`: {src}")]
+    ParsingGlob {
+        pattern: String,
+        src: globset::Error,
+    },
+
+    #[error("failed to read `{path}`: {src}")]
+    Reading { path: String, src: io::Error },
+
+    #[error("failed to install `{path}`: {src}")]
+    Writing { path: String, src: io::Error },
+
+    #[error("failed to remove `{path}`: {src}")]
+    Removing { path: String, src: io::Error },
+}
+
+
+pub(crate) type Installed = Manifest<Entry>;
+
+#[non_exhaustive]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub(crate) struct Entry {
+    pub path: PathBuf,
+    pub source: PathBuf,
+    pub mode: Mode,
+    pub hash: String,
+}
+
+impl ManifestEntry for Entry {
+    const FILENAME: &'static str = "installed.json";
+    const VERSION: u8 = 0;
+
+    fn path(&self) -> &Path {
+        &self.path
+    }
+
+    fn hash(&self) -> &str {
+        &self.hash
+    }
+}
+
+
+/// Installs every rendered output matching `[install.paths]` and removes
+/// previous installs that no longer match.
+pub(crate) fn install(config: &Config) -> crate::Result<()> {
+    let index = Index::load_or_create(&config.project.root)?;
+    let mut installed = Installed::load_or_create(&config.project.root)?;
+
+    let outputs = index
+        .entries
+        .values()
+        .map(|entry| (entry.template.as_str(), entry.path.as_path()));
+
+    sync(outputs, &config.install, &mut installed)?;
+
+    installed.save()?;
+
+    Ok(())
+}
+
+/// Removes every file placed by `they install`.
+pub(crate) fn uninstall(config: &Config) -> crate::Result<()> {
+    let mut installed = Installed::load_or_create(&config.project.root)?;
+
+    let paths: Vec<PathBuf> = installed.entries.keys().cloned().collect();
+
+    for path in paths {
+        remove(&path, &mut installed)?;
+    }
+
+    installed.save()?;
+
+    Ok(())
+}
+
+
+/// Places every `(template, rendered path)` output into the directories
+/// whose glob matches its template.
+fn sync<'a>(
+    outputs: impl Iterator<Item = (&'a str, &'a Path)>,
+    install: &ResolvedInstall,
+    installed: &mut Installed,
+) -> Result<()> {
+    let targets = install
+        .paths
+        .iter()
+        .map(|(pattern, dir)| {
+            let matcher = Glob::new(pattern)
+                .map_err(|src| Error::ParsingGlob {
+                    pattern: pattern.clone(),
+                    src,
+                })?
+                .compile_matcher();
+
+            Ok((matcher, dir.as_path()))
+        })
+        .collect::<Result<Vec<(GlobMatcher, &Path)>>>()?;
+    let mut placed: HashSet<PathBuf> = HashSet::new();
+
+    for (template, source) in outputs {
+        if !source.exists() {
+            debug!("skipping `{}` (not rendered)", source.display());
+
+            continue;
+        }
+
+        let Some(filename) = source.file_name() else {
+            continue;
+        };
+
+        for (_, dir) in targets.iter().filter(|(m, _)| m.is_match(template)) {
+            let path = dir.join(filename);
+
+            if !placed.insert(path.clone()) {
+                warn!(
+                    "conflict: `{}` is installed to more than once, keeping \
+                     the first",
+                    path.display()
+                );
+
+                continue;
+            }
+
+            place(source, &path, install.mode, installed)?;
+        }
+    }
+
+    for path in installed.find_orphans(&placed) {
+        remove(&path, installed)?;
+    }
+
+    Ok(())
+}
+
+fn place(
+    source: &Path,
+    path: &Path,
+    mode: Mode,
+    installed: &mut Installed,
+) -> Result<()> {
+    let hash = hash_file(source)?;
+    let exists = path.symlink_metadata().is_ok();
+
+    match installed.get(path) {
+        Some(entry) if exists && is_modified(entry)? => {
+            warn!(
+                "conflict: `{}` was modified since it was installed, skipping",
+                path.display()
+            );
+
+            return Ok(());
+        }
+        Some(entry)
+            if exists
+                && entry.mode == mode
+                && entry.source == source
+                && entry.hash == hash =>
+        {
+            debug!("`{}` is up to date", path.display());
+
+            return Ok(());
+        }
+        None if exists => {
+            warn!(
+                "conflict: `{}` already exists and wasn't installed by \
+                 theymer, skipping",
+                path.display()
+            );
+
+            return Ok(());
+        }
+        _ => {}
+    }
+
+    let writing = |src| Error::Writing {
+        path: path.display().to_string(),
+        src,
+    };
+
+    if exists {
+        fs::remove_file(path).map_err(writing)?;
+    }
+
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent).map_err(writing)?;
+    }
+
+    match mode {
+        Mode::Copy => fs::copy(source, path).map(drop),
+        Mode::Symlink => symlink(source, path),
+    }
+    .map_err(writing)?;
+
+    installed.insert(Entry {
+        path: path.to_path_buf(),
+        source: source.to_path_buf(),
+        mode,
+        hash,
+    });
+
+    info!("installed `{}` -> `{}`", source.display(), path.display());
+
+    Ok(())
+}
+
+fn remove(path: &Path, installed: &mut Installed) -> Result<()> {
+    let Some(entry) = installed.get(path) else {
+        return Ok(());
+    };
+
+    if path.symlink_metadata().is_err() {
+        debug!("forgetting `{}` (already deleted)", path.display());
+    } else if is_modified(entry)? {
+        warn!(
+            "conflict: `{}` was modified since it was installed, leaving it",
+            path.display()
+        );
+
+        return Ok(());
+    } else {
+        fs::remove_file(path).map_err(|src| Error::Removing {
+            path: path.display().to_string(),
+            src,
+        })?;
+
+        info!("uninstalled `{}`", path.display());
+    }
+
+    installed.remove(path);
+
+    Ok(())
+}
+
+/// Whether an installed file was changed or replaced by something else.
+fn is_modified(entry: &Entry) -> Result<bool> {
+    match entry.mode {
+        Mode::Copy => Ok(
+            entry.path.is_symlink() || hash_file(&entry.path)? != entry.hash
+        ),
+        Mode::Symlink => Ok(!fs::read_link(&entry.path)
+            .is_ok_and(|target| target == entry.source)),
+    }
+}
+
+fn hash_file(path: &Path) -> Result<String> {
+    let content = fs::read_to_string(path).map_err(|src| Error::Reading {
+        path: path.display().to_string(),
+        src,
+    })?;
+
+    Ok(manifest::hash(&content))
+}
+
+#[cfg(unix)]
+fn symlink(source: &Path, path: &Path) -> io::Result<()> {
+    use std::os::unix::fs as unix_fs;
+
+    unix_fs::symlink(source, path)
+}
+
+#[cfg(windows)]
+fn symlink(source: &Path, path: &Path) -> io::Result<()> {
+    use std::os::windows::fs as windows_fs;
+
+    windows_fs::symlink_file(source, path)
+}
+
+
+#[cfg(test)]
+mod tests {
+    use indexmap::IndexMap;
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+
+    const TEMPLATE: &str = "ports/helix/SCHEME.toml.jinja";
+    const HELIX: &str = "ports/helix/*";
+
+    #[test]
+    fn uninstall_removes_only_what_was_placed() {
+        let dir = tempfile::tempdir().expect("failed to create temp dir");
+        let source = dir.path().join("render/ports/helix/cutiepro.toml");
+        let target = dir.path().join("config/helix/themes");
+        let install = ResolvedInstall {
+            mode: Mode::Copy,
+            paths: IndexMap::from([(HELIX.to_owned(), target.clone())]),
+        };
+        let mut installed = Installed::new(dir.path(), Entry::VERSION);
+
+        fs::create_dir_all(source.parent().expect("source has a parent"))
+            .expect("failed to create render dir");
+        fs::write(&source, "\"ui.background\" = \"#181716\"\n")
+            .expect("failed to write rendered file");
+        let unrelated = target.join("other.toml");
+
+        fs::create_dir_all(&target).expect("failed to create target");
+        fs::write(&unrelated, "").expect("failed to write unrelated file");
+
+        sync(
+            [(TEMPLATE, source.as_path())].into_iter(),
+            &install,
+            &mut installed,
+        )
+        .unwrap_or_else(|e| panic!("failed to install: {e}"));
+
+        let placed = target.join("cutiepro.toml");
+
+        assert_eq!(
+            fs::read_to_string(&placed).ok(),
+            fs::read_to_string(&source).ok(),
+            "rendered file should be copied"
+        );
+
+        for path in [placed.clone(), unrelated.clone()] {
+            remove(&path, &mut installed)
+                .unwrap_or_else(|e| panic!("failed to uninstall: {e}"));
+        }
+
+        assert!(!placed.exists(), "installed file should be removed");
+        assert!(unrelated.exists(), "untracked file should be left alone");
+        assert!(installed.entries.is_empty(), "manifest should be empty");
+    }
+
+    #[test]
+    fn existing_files_are_not_overwritten() {
+        let dir = tempfile::tempdir().expect("failed to create temp dir");
+        let source = dir.path().join("render/ports/helix/cutiepro.toml");
+        let target = dir.path().join("config/helix/themes");
+        let install = ResolvedInstall {
+            mode: Mode::Copy,
+            paths: IndexMap::from([(HELIX.to_owned(), target.clone())]),
+        };
+        let mut installed = Installed::new(dir.path(), Entry::VERSION);
+
+        fs::create_dir_all(source.parent().expect("source has a parent"))
+            .expect("failed to create render dir");
+        fs::write(&source, "\"ui.background\" = \"#181716\"\n")
+            .expect("failed to write rendered file");
+        let existing = target.join("cutiepro.toml");
+
+        fs::create_dir_all(&target).expect("failed to create target");
+        fs::write(&existing, "mine").expect("failed to write existing file");
+
+        sync(
+            [(TEMPLATE, source.as_path())].into_iter(),
+            &install,
+            &mut installed,
+        )
+        .unwrap_or_else(|e| panic!("failed to install: {e}"));
+
+        assert_eq!(fs::read_to_string(&existing).ok().as_deref(), Some("mine"));
+        assert!(installed.entries.is_empty(), "file shouldn't be tracked");
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn symlinks_point_at_rendered_output() {
+        let dir = tempfile::tempdir().expect("failed to create temp dir");
+        let source = dir.path().join("render/ports/helix/cutiepro.toml");
+        let target = dir.path().join("config/helix/themes");
+        let install = ResolvedInstall {
+            mode: Mode::Symlink,
+            paths: IndexMap::from([(HELIX.to_owned(), target.clone())]),
+        };
+        let mut installed = Installed::new(dir.path(), Entry::VERSION);
+
+        fs::create_dir_all(source.parent().expect("source has a parent"))
+            .expect("failed to create render dir");
+        fs::write(&source, "\"ui.background\" = \"#181716\"\n")
+            .expect("failed to write rendered file");
+
+        sync(
+            [(TEMPLATE, source.as_path())].into_iter(),
+            &install,
+            &mut installed,
+        )
+        .unwrap_or_else(|e| panic!("failed to install: {e}"));
+
+        let placed = target.join("cutiepro.toml");
+
+        assert_eq!(fs::read_link(&placed).ok(), Some(source.clone()));
+
+        sync(std::iter::empty(), &install, &mut installed)
+            .unwrap_or_else(|e| panic!("failed to sync: {e}"));
+
+        assert!(
+            placed.symlink_metadata().is_err(),
+            "outputs that are no longer rendered should be uninstalled"
+        );
+    }
+}
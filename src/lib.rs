@@ -31,10 +31,15 @@ pub mod config;
 
 pub(crate) mod themes;
 
+mod atomic;
+mod diagnostics;
 mod extensions;
+mod import;
+mod install;
 mod manifest;
 mod output;
 mod render;
+mod scaffold;
 mod templates;
 
 pub use self::config::{Config, ProjectType};
@@ -43,9 +48,14 @@ pub(crate) use self::manifest::{Entry as ManifestEntry, Manifest};
 pub(crate) use self::themes::{Name as ThemeName, Scheme, SchemeName, Theme};
 
 use self::config::Error as ConfigError;
+use self::diagnostics::Diagnostic;
+use self::import::Error as ImportError;
+use self::install::Error as InstallError;
 use self::manifest::Error as ManifestError;
 use self::output::UpstreamError;
-use self::templates::{DirectiveError, ProviderError};
+use self::render::{InspectError, SelectionError, TemplatizeError};
+use self::scaffold::Error as ScaffoldError;
+use self::templates::{DirectiveError, LibraryError, ProviderError};
 use self::themes::{
     Error as ThemeError, NameError, RoleError, SchemeError, SwatchError,
 };
@@ -87,15 +97,54 @@ pub enum Error {
     #[error("directive error: {0}")]
     Directive(#[from] DirectiveError),
 
+    #[error("template library error: {0}")]
+    Library(#[from] LibraryError),
+
     #[error("git provider error: {0}")]
     Provider(#[from] ProviderError),
 
+    #[error("inspect error: {0}")]
+    Inspect(#[from] InspectError),
+
+    #[error("selection error: {0}")]
+    Selection(#[from] SelectionError),
+
+    #[error("templatize error: {0}")]
+    Templatize(#[from] TemplatizeError),
+
     #[error("error rendering: {0}")]
     Rendering(#[source] anyhow::Error),
 
+    #[error("{failed} output(s) or theme file(s) failed")]
+    Failed { failed: usize },
+
+    #[error("check failed: {failed} of {total} outputs and theme files failed")]
+    Check { failed: usize, total: usize },
+
+    #[error("{stale} of {total} outputs are out of date (rerun `they`)")]
+    Stale { stale: usize, total: usize },
+
+    #[error("file watcher error: {0}")]
+    Watch(#[from] notify::Error),
+
+    #[error("import error: {0}")]
+    Import(#[from] ImportError),
+
+    #[error("install error: {0}")]
+    Install(#[from] InstallError),
+
+    #[error("scaffolding error: {0}")]
+    Scaffold(#[from] ScaffoldError),
+
     #[error("upstream error: {0}")]
     Upstream(#[from] UpstreamError),
 
+    #[error("{error}{diagnostic}")]
+    Diagnosed {
+        error: Box<Self>,
+        diagnostic: Diagnostic,
+    },
+
     #[error("internal error in {module}: {reason}! this is a bug!")]
     InternalBug {
         module: &'static str,
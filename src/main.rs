@@ -6,8 +6,17 @@
 #![allow(missing_docs, reason = "TODO: add docs")]
 #![allow(clippy::missing_errors_doc, reason = "TODO: add docs")]
 
-use theymer::{Result, cli};
+use std::process::ExitCode;
 
-fn main() -> Result<()> {
-    cli::run()
+use theymer::cli;
+
+fn main() -> ExitCode {
+    // printed with `Display` so diagnostics keep their snippets
+    if let Err(e) = cli::run() {
+        eprintln!("error: {e}");
+
+        return ExitCode::FAILURE;
+    }
+
+    ExitCode::SUCCESS
 }
@@ -7,12 +7,16 @@ use std::{fmt, fs, io};
 
 use anyhow::Context as _;
 use indexmap::IndexMap;
+use log::debug;
 use serde::de::{DeserializeOwned, SeqAccess, Visitor};
 use serde::{Deserialize, Deserializer, Serialize, Serializer};
 use sha2::{Digest as _, Sha256};
 
+use crate::atomic;
 use crate::output::FileStatus;
 
+pub(crate) mod store;
+
 const DIR: &str = ".theymer";
 
 pub(crate) type Result<T> = StdResult<T, Error>;
@@ -45,6 +49,10 @@ pub(crate) struct Manifest<E: Entry> {
         deserialize_with = "deserialize_entries"
     )]
     pub entries: IndexMap<PathBuf, E>,
+
+    /// Where the manifest is saved to, under the project root.
+    #[serde(skip)]
+    pub file: PathBuf,
 }
 
 pub(crate) trait Entry: Clone + Serialize + DeserializeOwned {
@@ -53,51 +61,78 @@ pub(crate) trait Entry: Clone + Serialize + DeserializeOwned {
 
     fn path(&self) -> &Path;
     fn hash(&self) -> &str;
+
+    /// Brings an entry saved by an older `version` of the manifest up to
+    /// date.
+    fn migrate(&mut self, _version: u8) {}
 }
 
 impl<E: Entry> Manifest<E> {
-    pub(crate) fn new(version: u8) -> Self {
+    pub(crate) fn new(root: &Path, version: u8) -> Self {
         Self {
             version,
             entries: IndexMap::new(),
+            file: dir(root).join(E::FILENAME),
         }
     }
 
-    pub(crate) fn load_or_create() -> Result<Self> {
-        match fs::read_to_string(Self::file()) {
+    pub(crate) fn load_or_create(root: &Path) -> Result<Self> {
+        let file = dir(root).join(E::FILENAME);
+
+        match fs::read_to_string(&file) {
             Ok(content) => {
-                serde_json::from_str(&content).map_err(|src| Error::Parsing {
-                    file: Self::file().display().to_string(),
-                    src: Box::new(src),
-                })
+                let mut manifest: Self = serde_json::from_str(&content)
+                    .map_err(|src| Error::Parsing {
+                        file: file.display().to_string(),
+                        src: Box::new(src),
+                    })?;
+
+                manifest.file = file;
+
+                if manifest.version < E::VERSION {
+                    debug!(
+                        "migrating `{}` from version {} to {}",
+                        manifest.file.display(),
+                        manifest.version,
+                        E::VERSION
+                    );
+
+                    for entry in manifest.entries.values_mut() {
+                        entry.migrate(manifest.version);
+                    }
+
+                    manifest.version = E::VERSION;
+                }
+
+                Ok(manifest)
             }
             Err(e) if e.kind() == io::ErrorKind::NotFound => {
-                Ok(Self::new(E::VERSION))
+                Ok(Self::new(root, E::VERSION))
             }
             Err(src) => Err(Error::Reading {
-                file: Self::file().display().to_string(),
+                file: file.display().to_string(),
                 src,
             }),
         }
     }
 
     pub(crate) fn save(&self) -> Result<()> {
-        if let Some(parent) = Self::file().parent() {
+        if let Some(parent) = self.file.parent() {
             fs::create_dir_all(parent).map_err(|src| Error::CreatingDir {
-                path: DIR.to_owned(),
+                path: parent.display().to_string(),
                 src,
             })?;
         }
 
         let content = serde_json::to_string_pretty(self).map_err(|src| {
             Error::Parsing {
-                file: Self::file().display().to_string(),
+                file: self.file.display().to_string(),
                 src: Box::new(src),
             }
         })?;
 
-        fs::write(Self::file(), content).map_err(|src| Error::Writing {
-            file: Self::file().display().to_string(),
+        atomic::write(&self.file, content).map_err(|src| Error::Writing {
+            file: self.file.display().to_string(),
             src,
         })
     }
@@ -125,10 +160,11 @@ impl<E: Entry> Manifest<E> {
             .cloned()
             .collect()
     }
+}
 
-    fn file() -> PathBuf {
-        Path::new(DIR).join(E::FILENAME)
-    }
+/// Where theymer keeps its state for the project at `root`.
+fn dir(root: &Path) -> PathBuf {
+    root.join(DIR)
 }
 
 pub(crate) fn check_status<F>(
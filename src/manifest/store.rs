@@ -0,0 +1,87 @@
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+use std::{fs, io};
+
+use log::debug;
+
+use super::{Error, Result};
+use crate::atomic;
+
+const OBJECTS_DIR: &str = "objects";
+
+pub(crate) fn put(root: &Path, content: &str) -> Result<String> {
+    let hash = super::hash(content);
+    let file = object(root, &hash);
+
+    if file.exists() {
+        return Ok(hash);
+    }
+
+    fs::create_dir_all(dir(root)).map_err(|src| Error::CreatingDir {
+        path: dir(root).display().to_string(),
+        src,
+    })?;
+
+    atomic::write(&file, content).map_err(|src| Error::Writing {
+        file: file.display().to_string(),
+        src,
+    })?;
+
+    Ok(hash)
+}
+
+pub(crate) fn get(root: &Path, hash: &str) -> Result<Option<String>> {
+    let file = object(root, hash);
+
+    match fs::read_to_string(&file) {
+        Ok(content) => Ok(Some(content)),
+        Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(None),
+        Err(src) => Err(Error::Reading {
+            file: file.display().to_string(),
+            src,
+        }),
+    }
+}
+
+pub(crate) fn retain(root: &Path, hashes: &HashSet<&str>) -> Result<()> {
+    let entries = match fs::read_dir(dir(root)) {
+        Ok(entries) => entries,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(()),
+        Err(src) => {
+            return Err(Error::Reading {
+                file: dir(root).display().to_string(),
+                src,
+            });
+        }
+    };
+
+    let keep: HashSet<PathBuf> =
+        hashes.iter().map(|h| object(root, h)).collect();
+
+    for entry in entries.filter_map(io::Result::ok) {
+        let file = entry.path();
+
+        if keep.contains(&file) {
+            continue;
+        }
+
+        fs::remove_file(&file).map_err(|src| Error::Writing {
+            file: file.display().to_string(),
+            src,
+        })?;
+
+        debug!("removed unreferenced object `{}`", file.display());
+    }
+
+    Ok(())
+}
+
+fn dir(root: &Path) -> PathBuf {
+    super::dir(root).join(OBJECTS_DIR)
+}
+
+fn object(root: &Path, hash: &str) -> PathBuf {
+    let digest = hash.split_once(':').map_or(hash, |(_algo, hex)| hex);
+
+    dir(root).join(digest)
+}
@@ -0,0 +1,51 @@
+use std::path::Path;
+
+use owo_colors::OwoColorize as _;
+use owo_colors::Stream::Stdout;
+use similar::{ChangeTag, TextDiff};
+
+const CONTEXT_LINES: usize = 3;
+
+#[must_use]
+pub(crate) fn unified(path: &Path, old: &str, new: &str) -> String {
+    let diff = TextDiff::from_lines(old, new);
+
+    let header = format!("--- {0}\n+++ {0} (rendered)", path.display());
+    let mut lines = vec![
+        header
+            .if_supports_color(Stdout, |text| text.bold().to_string())
+            .to_string(),
+    ];
+
+    for hunk in diff
+        .unified_diff()
+        .context_radius(CONTEXT_LINES)
+        .iter_hunks()
+    {
+        lines.push(
+            hunk.header()
+                .if_supports_color(Stdout, |text| text.cyan().to_string())
+                .to_string(),
+        );
+
+        for change in hunk.iter_changes() {
+            let value = change.value().trim_end_matches('\n');
+
+            lines.push(match change.tag() {
+                ChangeTag::Delete => format!("-{value}")
+                    .if_supports_color(Stdout, |text| text.red().to_string())
+                    .to_string(),
+                ChangeTag::Insert => format!("+{value}")
+                    .if_supports_color(Stdout, |text| text.green().to_string())
+                    .to_string(),
+                ChangeTag::Equal => format!(" {value}"),
+            });
+
+            if change.missing_newline() {
+                lines.push("\\ No newline at end of file".to_owned());
+            }
+        }
+    }
+
+    lines.join("\n")
+}
@@ -0,0 +1,138 @@
+use std::path::Path;
+use std::sync::{Mutex, OnceLock, PoisonError};
+
+use clap::ValueEnum;
+use indexmap::IndexMap;
+use log::warn;
+use serde::Serialize;
+
+use super::Decision;
+
+
+static FORMAT: OnceLock<MessageFormat> = OnceLock::new();
+static SUMMARY: Mutex<Option<Summary>> = Mutex::new(None);
+
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, ValueEnum)]
+pub(crate) enum MessageFormat {
+    #[default]
+    Human,
+    Json,
+}
+
+#[non_exhaustive]
+#[derive(Debug, Serialize)]
+#[serde(tag = "event", rename_all = "snake_case")]
+pub(crate) enum Event<'a> {
+    /// What happened to a rendered output.
+    File {
+        decision: Decision,
+        path: &'a Path,
+        theme: &'a str,
+        scheme: &'a str,
+        template: &'a str,
+        dry_run: bool,
+    },
+
+    /// An output was changed by its formatter before being written.
+    Formatted {
+        path: &'a Path,
+    },
+
+    /// The upstream repo or file of an output couldn't be detected.
+    Upstream {
+        path: Option<&'a Path>,
+        message: &'a str,
+    },
+
+    Error {
+        message: &'a str,
+    },
+
+    Summary(Summary),
+}
+
+#[derive(Debug, Default, Clone, Serialize)]
+pub(crate) struct Summary {
+    pub success: bool,
+    pub files: IndexMap<Decision, usize>,
+    pub formatted: usize,
+    pub warnings: usize,
+    pub errors: usize,
+}
+
+
+pub(crate) fn init(format: MessageFormat) {
+    if FORMAT.set(format).is_err() {
+        warn!("message format was already set");
+    }
+}
+
+#[must_use]
+pub(crate) fn format() -> MessageFormat {
+    FORMAT.get().copied().unwrap_or_default()
+}
+
+/// Prints output meant for people, moved to stderr with `--message-format
+/// json` so stdout stays a stream of events.
+pub(crate) fn print(text: &str) {
+    if format() == MessageFormat::Json {
+        eprintln!("{text}");
+    } else {
+        println!("{text}");
+    }
+}
+
+pub(crate) fn emit(event: &Event<'_>) {
+    record(event);
+
+    if format() != MessageFormat::Json {
+        return;
+    }
+
+    match serde_json::to_string(event) {
+        Ok(json) => println!("{json}"),
+        Err(e) => warn!("failed to serialize event: {e}"),
+    }
+}
+
+/// Reports how the run went: its error, if any, then the summary if it
+/// rendered anything.
+pub(crate) fn finish<T>(result: &crate::Result<T>, rendered: bool) {
+    if let Err(e) = result {
+        emit(&Event::Error {
+            message: &e.to_string(),
+        });
+    }
+
+    if !rendered {
+        return;
+    }
+
+    let mut summary = SUMMARY
+        .lock()
+        .unwrap_or_else(PoisonError::into_inner)
+        .take()
+        .unwrap_or_default();
+
+    summary.success = result.is_ok();
+
+    emit(&Event::Summary(summary));
+}
+
+fn record(event: &Event<'_>) {
+    let mut guard = SUMMARY.lock().unwrap_or_else(PoisonError::into_inner);
+    let summary = guard.get_or_insert_default();
+
+    match event {
+        Event::File { decision, .. } => {
+            *summary.files.entry(*decision).or_default() += 1;
+        }
+        Event::Formatted { .. } => summary.formatted += 1,
+        Event::Upstream { .. } => summary.warnings += 1,
+        Event::Error { .. } => summary.errors += 1,
+        Event::Summary(_) => {}
+    }
+
+    drop(guard);
+}
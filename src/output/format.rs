@@ -1,11 +1,9 @@
 use std::collections::HashMap;
-use std::fs;
 use std::path::Path;
 use std::str::FromStr as _;
 
 use anyhow::Context as _;
 use json5format::Json5Format;
-use log::{debug, info};
 use strum::EnumString;
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, EnumString)]
@@ -28,47 +26,33 @@ impl FileType {
     }
 }
 
-pub(crate) fn format(path: &Path) -> anyhow::Result<bool> {
+pub(crate) fn format_content(
+    path: &Path,
+    content: &str,
+) -> anyhow::Result<String> {
     let Some(supported_type) = FileType::from(path) else {
-        return Ok(false);
+        return Ok(content.to_owned());
     };
 
-    match supported_type {
-        FileType::Json => json(path, json_format_options()),
+    let mut formatted = match supported_type {
+        FileType::Json => json(path, content, json_format_options())?,
         FileType::Jsonc | FileType::Json5 => {
-            json(path, jsonc_json5_format_options())
+            json(path, content, jsonc_json5_format_options())?
         }
-        FileType::Md => markdown(path),
-        FileType::Toml => toml(path),
-        FileType::Xml | FileType::Svg => xml(path),
-    }
-}
-
-fn toml(path: &Path) -> anyhow::Result<bool> {
-    let content = fs::read_to_string(path).with_context(|| {
-        format!("reading file `{}` for formatting", path.display())
-    })?;
-
-    let options = toml_options();
-    let mut formatted = taplo::formatter::format(&content, options);
+        FileType::Md => markdown(path, content)?,
+        FileType::Toml => toml(content),
+        FileType::Xml | FileType::Svg => xml(path, content)?,
+    };
 
     if !formatted.ends_with('\n') {
         formatted.push('\n');
     }
 
-    if formatted == content {
-        debug!("formatting unnecessary for `{}`", path.display());
-
-        Ok(false)
-    } else {
-        fs::write(path, formatted).with_context(|| {
-            format!("writing formatted file `{}`", path.display())
-        })?;
-
-        info!("formatted `{}`", path.display());
+    Ok(formatted)
+}
 
-        Ok(true)
-    }
+fn toml(content: &str) -> String {
+    taplo::formatter::format(content, toml_options())
 }
 
 fn toml_options() -> taplo::formatter::Options {
@@ -96,39 +80,19 @@ fn toml_options() -> taplo::formatter::Options {
     }
 }
 
-fn markdown(path: &Path) -> anyhow::Result<bool> {
-    let content = fs::read_to_string(path).with_context(|| {
-        format!("reading file `{}` for formatting", path.display())
-    })?;
-
+fn markdown(path: &Path, content: &str) -> anyhow::Result<String> {
     let arena = comrak::Arena::new();
 
     let options = markdown_options();
 
-    let root = comrak::parse_document(&arena, &content, &options);
+    let root = comrak::parse_document(&arena, content, &options);
 
     let mut formatted = String::new();
     comrak::format_commonmark(root, &options, &mut formatted).with_context(
         || format!("formatting markdown file `{}`", path.display()),
     )?;
 
-    if !formatted.ends_with('\n') {
-        formatted.push('\n');
-    }
-
-    if formatted == content {
-        debug!("formatting unnecessary for `{}`", path.display());
-
-        Ok(false)
-    } else {
-        fs::write(path, formatted).with_context(|| {
-            format!("writing formatted file `{}`", path.display())
-        })?;
-
-        info!("formatted `{}`", path.display());
-
-        Ok(true)
-    }
+    Ok(formatted)
 }
 
 fn markdown_options<'a>() -> comrak::Options<'a> {
@@ -217,18 +181,15 @@ fn jsonc_json5_format_options() -> json5format::FormatOptions {
 
 fn json(
     path: &Path,
+    content: &str,
     options: json5format::FormatOptions,
-) -> anyhow::Result<bool> {
-    let content = fs::read_to_string(path).with_context(|| {
-        format!("reading file `{}` for formatting", path.display())
-    })?;
-
+) -> anyhow::Result<String> {
     let format = Json5Format::with_options(options).with_context(|| {
         format!("creating json5 formatter for `{}`", path.display())
     })?;
 
     let parsed = json5format::ParsedDocument::from_str(
-        &content,
+        content,
         Some(path.display().to_string()),
     )
     .with_context(|| format!("parsing json file `{}`", path.display()))?;
@@ -237,43 +198,20 @@ fn json(
         format!("formatting json file `{}`", path.display())
     })?;
 
-    let mut formatted =
-        String::from_utf8(formatted_bytes).with_context(|| {
-            format!(
-                "converting formatted json to utf-8 for `{}`",
-                path.display()
-            )
-        })?;
-
-    if !formatted.ends_with('\n') {
-        formatted.push('\n');
-    }
-
-    if formatted == content {
-        debug!("formatting unnecessary for `{}`", path.display());
-
-        Ok(false)
-    } else {
-        fs::write(path, formatted).with_context(|| {
-            format!("writing formatted file `{}`", path.display())
-        })?;
-
-        info!("formatted `{}`", path.display());
-
-        Ok(true)
-    }
+    String::from_utf8(formatted_bytes).with_context(|| {
+        format!(
+            "converting formatted json to utf-8 for `{}`",
+            path.display()
+        )
+    })
 }
 
-fn xml(path: &Path) -> anyhow::Result<bool> {
+fn xml(path: &Path, content: &str) -> anyhow::Result<String> {
     use quick_xml::events::Event;
 
-    let content = fs::read_to_string(path).with_context(|| {
-        format!("reading file `{}` for formatting", path.display())
-    })?;
-
     let (indent_char, indent_size) = xml_format_options();
 
-    let mut reader = quick_xml::Reader::from_str(&content);
+    let mut reader = quick_xml::Reader::from_str(content);
     reader.config_mut().check_comments = true;
     reader.config_mut().enable_all_checks(true);
 
@@ -299,31 +237,9 @@ fn xml(path: &Path) -> anyhow::Result<bool> {
         }
     }
 
-    let mut formatted =
-        String::from_utf8(writer.into_inner()).with_context(|| {
-            format!(
-                "converting formatted xml to utf-8 for `{}`",
-                path.display()
-            )
-        })?;
-
-    if !formatted.ends_with('\n') {
-        formatted.push('\n');
-    }
-
-    if formatted == content {
-        debug!("formatting unnecessary for `{}`", path.display());
-
-        Ok(false)
-    } else {
-        fs::write(path, formatted).with_context(|| {
-            format!("writing formatted file `{}`", path.display())
-        })?;
-
-        info!("formatted `{}`", path.display());
-
-        Ok(true)
-    }
+    String::from_utf8(writer.into_inner()).with_context(|| {
+        format!("converting formatted xml to utf-8 for `{}`", path.display())
+    })
 }
 
 const fn xml_format_options() -> (u8, usize) {
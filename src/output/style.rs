@@ -1,6 +1,6 @@
 use serde::Serialize;
 
-#[derive(Debug, Default)]
+#[derive(Debug, Default, Serialize)]
 pub(crate) struct Style {
     pub color: ColorStyle,
     pub text: TextStyle,
@@ -6,6 +6,8 @@ use git2::Repository;
 use indexmap::IndexMap;
 use log::{info, warn};
 
+use super::{Event, events};
+
 #[non_exhaustive]
 #[derive(Debug, thiserror::Error)]
 pub(crate) enum Error {
@@ -106,15 +108,29 @@ impl Cache {
         render_path: &Path,
     ) -> Option<Upstream> {
         let Ok(repo) = Repository::discover(render_path) else {
-            warn!(
+            let message = format!(
                 "failed to discover git repo from path `{}`",
                 render_path.display()
             );
+
+            warn!("{message}");
+            events::emit(&Event::Upstream {
+                path: Some(render_path),
+                message: &message,
+            });
+
             return None;
         };
 
         let Some(root) = repo.workdir() else {
-            warn!("git repo has no working dir (bare repo?)");
+            let message = "git repo has no working dir (bare repo?)";
+
+            warn!("{message}");
+            events::emit(&Event::Upstream {
+                path: Some(render_path),
+                message,
+            });
+
             return None;
         };
 
@@ -127,7 +143,16 @@ impl Cache {
         let info = Upstream::from_repo(&repo, root.clone()).ok();
 
         if info.is_none() {
-            warn!("failed to extract info from repo at `{}`", root.display());
+            let message = format!(
+                "failed to extract info from repo at `{}`",
+                root.display()
+            );
+
+            warn!("{message}");
+            events::emit(&Event::Upstream {
+                path: Some(render_path),
+                message: &message,
+            });
         }
 
         self.0.insert(root, info.clone());
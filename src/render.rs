@@ -1,59 +1,207 @@
 use crate::{ProjectType, ThemeName};
-use std::fs;
+use std::collections::{BTreeSet, HashSet};
+use std::num::NonZeroUsize;
 use std::path::{Path, PathBuf};
+use std::result::Result as StdResult;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::{fs, io, thread};
 
-use anyhow::Context as _;
+use anyhow::{Context as _, anyhow};
 use indexmap::IndexMap;
 use log::{debug, info, warn};
 
+use crate::manifest::store;
 use crate::output::upstream::{Cache, Special};
-use crate::output::{Decision, Upstream, WriteMode, format, strategy};
+use crate::output::{
+    Decision, Event, FileStatus, Upstream, WriteMode, diff, events,
+    format_content, strategy,
+};
 use crate::templates::{
-    Directives, JINJA_TEMPLATE_SUFFIX, Loader, ResolvedProvider,
+    self, Directives, JINJA_TEMPLATE_SUFFIX, Loader, ResolvedProvider,
     SET_TEST_OBJECT, SKIP_RENDERING_PREFIX, providers,
 };
-use crate::{Config, Error, Result, Scheme, Theme};
+use crate::{Config, Error, ManifestEntry as _, Result, Scheme, Theme, atomic};
 
+mod check;
+mod conflict;
 mod context;
+mod failures;
 mod index;
+mod inspect;
+mod merge;
 mod objects;
+mod pool;
+mod prune;
+mod selection;
+mod templatize;
+mod transaction;
+mod verify;
+mod watch;
 
-use self::index::Index;
+use self::conflict::Resolution;
+use self::context::Context;
+use self::merge::Outcome;
 use self::objects::Color;
+use self::transaction::Transaction;
+
+pub(crate) use self::check::all as check;
+pub(crate) use self::failures::Failures;
+pub(crate) use self::index::{Dependencies, Index};
+pub(crate) use self::inspect::{
+    Error as InspectError, Format as InspectFormat, Target as InspectTarget,
+    context as inspect,
+};
+pub(crate) use self::selection::{Error as SelectionError, Selection};
+pub(crate) use self::templatize::{
+    Error as TemplatizeError, Target as TemplatizeTarget, file as templatize,
+};
+pub(crate) use self::verify::all as verify;
+pub(crate) use self::watch::run as watch;
 
 const THEME_MARKER: &str = "THEME";
 const SCHEME_MARKER: &str = "SCHEME";
 const SWATCH_MARKER: &str = "SWATCH";
 const SWATCH_VARIABLE: &str = "swatch";
 
+#[non_exhaustive]
+#[expect(clippy::struct_excessive_bools, reason = "render flags")]
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct Options {
+    pub write_mode: WriteMode,
+    pub dry_run: bool,
+    pub diff: bool,
+    pub prune: bool,
+    pub interactive: bool,
+
+    /// Stop at the first output that fails to render instead of reporting
+    /// every failure at the end.
+    pub fail_fast: bool,
+
+    /// Undo every write of the run if any output fails.
+    pub transactional: bool,
+
+    /// How many outputs to render at once. `None` uses every available core.
+    pub jobs: Option<NonZeroUsize>,
+}
+
+impl Options {
+    fn threads(&self) -> NonZeroUsize {
+        self.jobs
+            .or_else(|| thread::available_parallelism().ok())
+            .unwrap_or(NonZeroUsize::MIN)
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct Job<'a> {
+    pub theme: &'a Theme,
+    pub scheme: &'a Scheme,
+    pub template_name: &'a str,
+    pub template: &'a minijinja::Template<'a, 'a>,
+    pub directives: &'a Directives,
+    pub swatch: Option<&'a str>,
+}
+
+/// What happens to an output, decided before it's rendered so up to date
+/// outputs never are.
+#[derive(Debug)]
+struct Plan {
+    path: PathBuf,
+    decision: Decision,
+
+    /// `None` when the output is skipped.
+    rendered: Option<Rendered>,
+}
+
+/// An output rendered and formatted, waiting to be written.
+#[derive(Debug)]
+struct Rendered {
+    formatted: String,
+
+    /// Whether the formatter changed what the template produced.
+    reformatted: bool,
+
+    dependencies: Dependencies,
+}
+
 #[non_exhaustive]
 #[derive(Debug)]
 pub(crate) struct Session {
+    pub root: PathBuf,
     pub index: Index,
     pub providers: Vec<ResolvedProvider>,
     pub git_cache: Cache,
-    pub write_mode: WriteMode,
-    pub dry_run: bool,
+    pub options: Options,
+    pub rendered: HashSet<PathBuf>,
+    pub failures: Failures,
+    pub resolve_all: Option<Resolution>,
+    pub transaction: Transaction,
 }
 
 impl Session {
     fn new(
+        root: &Path,
         providers: Vec<ResolvedProvider>,
-        write_mode: WriteMode,
-        dry_run: bool,
+        options: Options,
     ) -> Result<Self> {
         Ok(Self {
-            index: Index::load_or_create()?,
+            root: root.to_path_buf(),
+            index: Index::load_or_create(root)?,
             providers,
             git_cache: Cache::new(),
-            write_mode,
-            dry_run,
+            options,
+            rendered: HashSet::new(),
+            failures: Failures::default(),
+            resolve_all: None,
+            transaction: Transaction::default(),
         })
     }
 
-    fn save(self) -> Result<()> {
-        if !self.dry_run {
+    fn in_memory(root: &Path, providers: Vec<ResolvedProvider>) -> Self {
+        Self {
+            root: root.to_path_buf(),
+            index: Index::new(root, index::Entry::VERSION),
+            providers,
+            git_cache: Cache::new(),
+            options: Options {
+                write_mode: WriteMode::Smart,
+                dry_run: true,
+                diff: false,
+                prune: false,
+                interactive: false,
+                fail_fast: false,
+                transactional: false,
+                jobs: None,
+            },
+            rendered: HashSet::new(),
+            failures: Failures::default(),
+            resolve_all: None,
+            transaction: Transaction::default(),
+        }
+    }
+
+    /// Backs up `path` before it's changed, if the run is transactional.
+    fn track(&mut self, path: &Path) -> anyhow::Result<()> {
+        if self.options.transactional {
+            self.transaction.record(path)?;
+        }
+
+        Ok(())
+    }
+
+    fn save(&self) -> Result<()> {
+        if !self.options.dry_run {
             self.index.save()?;
+
+            store::retain(
+                &self.root,
+                &self
+                    .index
+                    .entries
+                    .values()
+                    .map(|entry| entry.render_hash.as_str())
+                    .collect(),
+            )?;
         }
 
         Ok(())
@@ -154,12 +302,18 @@ fn resolve_with_autodetect(
     git_cache: &mut Cache,
 ) -> Option<(Upstream, PathBuf)> {
     let abs_path = render_path.canonicalize().ok().or_else(|| {
-        warn!(
+        let message = format!(
             "auto-detect mode... failed to canonicalize render path `{}`; \
              file may not exist yet",
             render_path.display()
         );
 
+        warn!("{message}");
+        events::emit(&Event::Upstream {
+            path: Some(render_path),
+            message: &message,
+        });
+
         None
     })?;
 
@@ -191,7 +345,14 @@ fn build_upstream(
     ) else {
         // FIXME: error handling
         let provider = git_info.url.host().unwrap_or("unknown");
-        warn!("failed to build blob url for host `{provider}`");
+        let message = format!("failed to build blob url for host `{provider}`");
+
+        warn!("{message}");
+        events::emit(&Event::Upstream {
+            path: Some(render_path),
+            message: &message,
+        });
+
         return Special::default();
     };
 
@@ -209,278 +370,776 @@ fn should_render(name: &str) -> bool {
         .any(|p| p.starts_with(SKIP_RENDERING_PREFIX))
 }
 
-fn prepare(
-    path: &Path,
-    theme: &Theme,
-    scheme: &Scheme,
-    template_name: &str,
-    template: &minijinja::Template<'_, '_>,
-    directives: &Directives,
-    special: &Special,
-    current_swatch: Option<&str>,
-) -> anyhow::Result<String> {
-    let context = context::build(
+fn jobs<'a>(
+    theme: &'a Theme,
+    scheme: &'a Scheme,
+    template_name: &'a str,
+    template: &'a minijinja::Template<'a, 'a>,
+    directives: &'a Directives,
+) -> Vec<Job<'a>> {
+    let job = Job {
         theme,
         scheme,
+        template_name,
+        template,
+        directives,
+        swatch: None,
+    };
+
+    if uses_swatch_iteration(template_name) {
+        scheme
+            .palette
+            .iter()
+            .map(|swatch| Job {
+                swatch: Some(swatch.name.as_str()),
+                ..job
+            })
+            .collect()
+    } else {
+        vec![job]
+    }
+}
+
+fn each_job<F>(
+    templates: &Loader,
+    themes: &IndexMap<ThemeName, Theme>,
+    mut f: F,
+) -> anyhow::Result<()>
+where
+    F: FnMut(&Job<'_>) -> anyhow::Result<()>,
+{
+    let with_directives = templates.with_directives()?;
+
+    for theme in themes.values() {
+        for scheme in theme.schemes.values() {
+            for (template_name, (template, directives)) in &with_directives {
+                if !should_render(template_name) {
+                    continue;
+                }
+
+                for job in
+                    jobs(theme, scheme, template_name, template, directives)
+                {
+                    f(&job)?;
+                }
+            }
+        }
+    }
+
+    Ok(())
+}
+
+fn build_context(job: &Job<'_>, special: &Special) -> anyhow::Result<Context> {
+    let context = context::build(
+        job.theme,
+        job.scheme,
         special,
-        &directives.style,
-        current_swatch,
+        &job.directives.style,
+        job.swatch,
     )?;
 
     if !context.contains_key(SET_TEST_OBJECT) {
         return Err(Error::InternalBug {
             module: "render",
             reason: format!(
-                "scheme `{}` context for template `{template_name}` missing \
+                "scheme `{}` context for template `{}` missing \
                  `{SET_TEST_OBJECT}` template variable",
-                scheme.name.as_str()
+                job.scheme.name.as_str(),
+                job.template_name,
             ),
         }
         .into());
     }
 
-    let rendered = template.render(&context).with_context(|| {
-        format!(
-            "rendering template `{template_name}` with scheme `{}`",
-            scheme.name.as_str()
-        )
-    })?;
+    Ok(context)
+}
 
-    let header = directives.make_header(path);
+/// Renders `job` with its header. Also returns the context values and the
+/// partials the render read.
+fn prepare(
+    path: &Path,
+    job: &Job<'_>,
+    context: &Context,
+) -> anyhow::Result<(String, BTreeSet<String>, BTreeSet<String>)> {
+    // whatever an earlier failed render loaded on this thread
+    templates::take_loaded();
+
+    let rendered = job
+        .template
+        .render(context.to_value())
+        .map_err(|e| job.directives.lines.error(e))
+        .with_context(|| {
+            format!(
+                "rendering template `{}` with scheme `{}`",
+                job.template_name,
+                job.scheme.name.as_str()
+            )
+        })?;
 
-    Ok(format!("{header}{rendered}"))
+    let header = job.directives.make_header(path);
+
+    Ok((
+        format!("{header}{rendered}"),
+        context.take_reads(),
+        templates::take_loaded(),
+    ))
+}
+
+/// Finds where `job` renders to and what its upstream is. Needs the session
+/// for the git cache, so it always runs on the calling thread.
+fn locate(
+    job: &Job<'_>,
+    config: &Config,
+    session: &mut Session,
+) -> anyhow::Result<(PathBuf, Special)> {
+    let scheme_name = job.scheme.name.as_str();
+    let path = resolve_path(
+        job.theme,
+        job.template_name,
+        scheme_name,
+        config,
+        job.swatch,
+    )?;
+    let special = build_upstream(scheme_name, &path, session, config);
+
+    Ok((path, special))
+}
+
+/// Renders and formats `job` like a real run would, without touching the
+/// index.
+fn render(
+    job: &Job<'_>,
+    config: &Config,
+    session: &mut Session,
+) -> anyhow::Result<(PathBuf, String)> {
+    let (path, special) = locate(job, config, session)?;
+    let context = build_context(job, &special)?;
+    let (output, ..) = prepare(&path, job, &context)?;
+    let formatted = format_content(&path, &output)?;
+
+    Ok((path, formatted))
+}
+
+/// Renders and formats `job` like a real run would, along with what the
+/// index knows about its output.
+fn render_formatted(
+    job: &Job<'_>,
+    config: &Config,
+    session: &mut Session,
+) -> anyhow::Result<(PathBuf, String, FileStatus)> {
+    let (path, special) = locate(job, config, session)?;
+    let context = build_context(job, &special)?;
+    let settings = index::hash_settings(config, job.directives)?;
+    let status =
+        session
+            .index
+            .check(&path, job.template, &context, &settings)?;
+    let (output, ..) = prepare(&path, job, &context)?;
+    let formatted = format_content(&path, &output)?;
+
+    Ok((path, formatted, status))
+}
+
+/// Decides what happens to `job`, then renders and formats it unless it's
+/// skipped. Only reads from the index, so it can run on any thread.
+fn produce(
+    job: &Job<'_>,
+    path: PathBuf,
+    special: &Special,
+    index: &Index,
+    config: &Config,
+    write_mode: WriteMode,
+) -> anyhow::Result<Plan> {
+    let context = build_context(job, special)?;
+    let settings = index::hash_settings(config, job.directives)?;
+    let status = index.check(&path, job.template, &context, &settings)?;
+    let decision = strategy::decide(status, write_mode);
+
+    if decision == Decision::Skip {
+        return Ok(Plan {
+            path,
+            decision,
+            rendered: None,
+        });
+    }
+
+    let (output, names, partials) = prepare(&path, job, &context)?;
+    let dependencies = Dependencies {
+        hash: index::hash_dependencies(
+            job.template,
+            &context,
+            &names,
+            &partials,
+        )?,
+        names,
+        partials,
+        settings,
+    };
+    let formatted = format_content(&path, &output)?;
+
+    Ok(Plan {
+        path,
+        decision,
+        rendered: Some(Rendered {
+            reformatted: formatted != output,
+            formatted,
+            dependencies,
+        }),
+    })
 }
 
 fn execute(
     decision: Decision,
     path: &Path,
-    output: &str,
-    theme: &Theme,
-    scheme: &Scheme,
-    template: &minijinja::Template<'_, '_>,
+    rendered: &Rendered,
+    job: &Job<'_>,
     session: &mut Session,
 ) -> anyhow::Result<()> {
     match decision {
-        // TODO: add interactive mode (possibly as default behavior?)
         Decision::Conflict => {
+            match merge::three_way(path, &rendered.formatted, session)? {
+                Outcome::Clean(merged) => {
+                    merge::write(
+                        path,
+                        &merged,
+                        &rendered.formatted,
+                        &rendered.dependencies,
+                        job,
+                        session,
+                    )?;
+                }
+                Outcome::Conflicted(conflicted) => {
+                    merge::report(path, &conflicted);
+
+                    resolve_conflict(path, rendered, job, session)?;
+                }
+                Outcome::Unavailable => {
+                    resolve_conflict(path, rendered, job, session)?;
+                }
+            }
+        }
+        _ if decision.should_write() => {
+            write_output(decision, path, rendered, job, session)?;
+        }
+        _ => {
+            debug!("skipped `{}` ({})", path.display(), decision.log_action());
+        }
+    }
+
+    Ok(())
+}
+
+fn resolve_conflict(
+    path: &Path,
+    rendered: &Rendered,
+    job: &Job<'_>,
+    session: &mut Session,
+) -> anyhow::Result<()> {
+    match conflict::resolve(path, &rendered.formatted, session)? {
+        Some(Resolution::Overwrite) => {
+            write_output(Decision::Overwrite, path, rendered, job, session)?;
+        }
+        Some(Resolution::Adopt) => {
+            conflict::adopt(path, &rendered.dependencies, job, session)?;
+        }
+        Some(Resolution::Keep) => {
+            info!("kept `{}` (last modified by user)", path.display());
+        }
+        None => {
             warn!(
                 "conflict: `{}` (last modified by user; use `--force` to \
                  overwrite)",
                 path.display()
             );
         }
-        _ if decision.should_write() => {
-            if session.dry_run {
-                info!(
-                    "would write `{}` ({})",
-                    path.display(),
-                    decision.log_action()
-                );
-            } else {
-                if let Some(parent) = path.parent() {
-                    fs::create_dir_all(parent).with_context(|| {
-                        format!("writing file `{}`", path.display())
-                    })?;
-                }
+    }
 
-                fs::write(path, output).with_context(|| {
-                    format!("writing file `{}`", path.display())
-                })?;
+    Ok(())
+}
 
-                format(path)?;
+fn write_output(
+    decision: Decision,
+    path: &Path,
+    rendered: &Rendered,
+    job: &Job<'_>,
+    session: &mut Session,
+) -> anyhow::Result<()> {
+    let formatted = &rendered.formatted;
 
-                let formatted =
-                    fs::read_to_string(path).with_context(|| {
-                        format!("reading file `{}` for hashing", path.display())
-                    })?;
+    if session.options.dry_run {
+        info!(
+            "would write `{}` ({})",
+            path.display(),
+            decision.log_action()
+        );
 
-                let entry = Index::create_entry(
-                    path, theme, scheme, template, &formatted,
-                )?;
+        if session.options.diff {
+            print_diff(path, formatted)?;
+        }
 
-                session.index.insert(entry);
+        return Ok(());
+    }
 
-                info!("generated `{}`", path.display());
-            }
+    // rewriting identical content would only bump the mtime and wake up
+    // anything watching the file
+    if fs::read_to_string(path).is_ok_and(|current| current == *formatted) {
+        debug!("`{}` is unchanged, not rewriting", path.display());
+    } else {
+        session.track(path)?;
+
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent).with_context(|| {
+                format!("writing file `{}`", path.display())
+            })?;
         }
-        _ => {
-            debug!("skipped `{}` ({})", path.display(), decision.log_action());
+
+        atomic::write(path, formatted)
+            .with_context(|| format!("writing file `{}`", path.display()))?;
+
+        if rendered.reformatted {
+            info!("formatted `{}`", path.display());
+
+            events::emit(&Event::Formatted { path });
+        }
+
+        info!("generated `{}`", path.display());
+    }
+
+    store::put(&session.root, formatted)?;
+
+    session.index.insert(Index::create_entry(
+        path,
+        job.theme,
+        job.scheme,
+        job.template,
+        formatted,
+        &rendered.dependencies,
+    ));
+
+    Ok(())
+}
+
+fn print_diff(path: &Path, formatted: &str) -> anyhow::Result<()> {
+    let current = match fs::read_to_string(path) {
+        Ok(content) => content,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => String::new(),
+        Err(e) => {
+            return Err(e).with_context(|| {
+                format!("reading file `{}` for diffing", path.display())
+            });
         }
+    };
+
+    if current != formatted {
+        events::print(&diff::unified(path, &current, formatted));
     }
 
     Ok(())
 }
 
 fn write(
-    theme: &Theme,
-    scheme: &Scheme,
-    template_name: &str,
-    template: &minijinja::Template<'_, '_>,
-    directives: &Directives,
+    job: &Job<'_>,
     config: &Config,
     session: &mut Session,
-    current_swatch: Option<&str>,
 ) -> anyhow::Result<()> {
-    let scheme_name = scheme.name.as_str();
-    let path = resolve_path(
-        theme,
-        template_name,
-        scheme_name,
-        config,
-        current_swatch,
-    )?;
-    let special = build_upstream(scheme_name, &path, session, config);
-    let output = prepare(
-        &path,
-        theme,
-        scheme,
-        template_name,
-        template,
-        directives,
+    let (path, special) = locate(job, config, session)?;
+    let plan = produce(
+        job,
+        path,
         &special,
-        current_swatch,
+        &session.index,
+        config,
+        session.options.write_mode,
     )?;
-    let status = session.index.check(&path, theme, scheme, template)?;
-    let decision = strategy::decide(status, session.write_mode);
 
-    execute(decision, &path, &output, theme, scheme, template, session)?;
-
-    Ok(())
+    commit(job, &plan, session)
 }
 
-pub(crate) fn apply(
-    theme: &Theme,
-    scheme: &Scheme,
-    template_name: &str,
-    template: &minijinja::Template<'_, '_>,
-    directives: &Directives,
-    config: &Config,
+/// Reports what was decided for an output and carries it out.
+fn commit(
+    job: &Job<'_>,
+    plan: &Plan,
     session: &mut Session,
-) -> Result<()> {
-    apply_internal(
-        theme,
-        scheme,
-        template_name,
-        template,
-        directives,
-        config,
-        session,
-    )
-    .map_err(Error::rendering)
+) -> anyhow::Result<()> {
+    let Plan {
+        path,
+        decision,
+        rendered,
+    } = plan;
+
+    events::emit(&Event::File {
+        decision: *decision,
+        path,
+        theme: job.theme.name.as_str(),
+        scheme: job.scheme.name.as_str(),
+        template: job.template_name,
+        dry_run: session.options.dry_run,
+    });
+
+    session.rendered.insert(path.clone());
+
+    let Some(rendered) = rendered else {
+        debug!("skipped `{}` ({})", path.display(), decision.log_action());
+
+        return Ok(());
+    };
+
+    execute(*decision, path, rendered, job, session)
 }
 
-fn apply_internal(
-    theme: &Theme,
-    scheme: &Scheme,
-    template_name: &str,
-    template: &minijinja::Template<'_, '_>,
-    directives: &Directives,
+/// Renders `jobs` on a thread pool, then writes the outputs in job order, so
+/// the index, events and failures come out the same as in a sequential run.
+fn run(
+    jobs: &[Job<'_>],
     config: &Config,
     session: &mut Session,
 ) -> anyhow::Result<()> {
-    if uses_swatch_iteration(template_name) {
-        if !template.source().contains(SWATCH_VARIABLE) {
-            warn!(
-                "template `{template_name}` has `{SWATCH_MARKER}` in filename \
-                 but doesn't use {SWATCH_VARIABLE} inside template",
-            );
-        }
+    let located: Vec<_> = jobs
+        .iter()
+        .map(|job| locate(job, config, session))
+        .collect();
+    let index = &session.index;
+    let write_mode = session.options.write_mode;
+    let fail_fast = session.options.fail_fast;
+    let cancel = AtomicBool::new(false);
+    let plans = pool::map(
+        jobs.iter().zip(located).collect(),
+        session.options.threads(),
+        &cancel,
+        |(job, located)| {
+            let plan = located.and_then(|(path, special)| {
+                produce(job, path, &special, index, config, write_mode)
+            });
 
-        for swatch in &scheme.palette {
-            write(
-                theme,
-                scheme,
-                template_name,
-                template,
-                directives,
-                config,
-                session,
-                Some(swatch.name.as_str()),
-            )?;
+            if fail_fast && plan.is_err() {
+                cancel.store(true, Ordering::Relaxed);
+            }
+
+            plan
+        },
+    );
+    let mut plans = plans.into_iter();
+
+    for (job, plan) in jobs.iter().zip(&mut plans) {
+        // only outputs after a failure with `--fail-fast` are skipped, and
+        // the failure may come later in job order
+        let Some(plan) = plan else {
+            return plans
+                .flatten()
+                .find_map(StdResult::err)
+                .map_or(Ok(()), Err);
+        };
+
+        if let Err(e) = plan.and_then(|plan| commit(job, &plan, session)) {
+            if session.options.fail_fast {
+                return Err(e);
+            }
+
+            session.failures.push(job, &e);
         }
-    } else {
-        write(
-            theme,
-            scheme,
-            template_name,
-            template,
-            directives,
-            config,
-            session,
-            None,
-        )?;
     }
 
     Ok(())
 }
 
-pub(crate) fn all_with(
-    theme: &Theme,
-    scheme: &Scheme,
+fn warn_unused_swatch(
+    template_name: &str,
+    template: &minijinja::Template<'_, '_>,
+) {
+    if uses_swatch_iteration(template_name)
+        && !template.source().contains(SWATCH_VARIABLE)
+    {
+        warn!(
+            "template `{template_name}` has `{SWATCH_MARKER}` in filename but \
+             doesn't use {SWATCH_VARIABLE} inside template",
+        );
+    }
+}
+
+pub(crate) fn all(
     templates: &Loader,
+    themes: &IndexMap<ThemeName, Theme>,
+    selection: &Selection,
     config: &Config,
-    session: &mut Session,
+    options: Options,
+    failures: Failures,
 ) -> Result<()> {
-    all_with_internal(theme, scheme, templates, config, session)
-        .map_err(Error::rendering)
+    let failed =
+        all_internal(templates, themes, selection, config, options, failures)
+            .map_err(Error::rendering)?;
+
+    if failed > 0 {
+        return Err(Error::Failed { failed });
+    }
+
+    Ok(())
 }
 
-fn all_with_internal(
-    theme: &Theme,
-    scheme: &Scheme,
+fn all_internal(
     templates: &Loader,
+    themes: &IndexMap<ThemeName, Theme>,
+    selection: &Selection,
     config: &Config,
+    options: Options,
+    failures: Failures,
+) -> anyhow::Result<usize> {
+    let mut session = Session::new(
+        &config.project.root,
+        templates.providers.clone(),
+        options,
+    )?;
+
+    session.failures = failures;
+
+    let result =
+        render_selection(templates, themes, selection, config, &mut session);
+
+    conclude(&mut session, result)
+}
+
+/// Saves the index after a run, or undoes the run if it's transactional and
+/// anything failed, saving included. Returns how many outputs and theme
+/// files failed.
+fn conclude(
     session: &mut Session,
-) -> anyhow::Result<()> {
-    for (template_name, (template, directives)) in
-        templates.with_directives()?
-    {
-        if !should_render(template_name) {
-            continue;
+    result: anyhow::Result<()>,
+) -> anyhow::Result<usize> {
+    if !session.options.transactional {
+        // whatever was written before an error is still recorded, so the
+        // index never disagrees with what's on disk
+        let saved = session.save().map_err(Into::into);
+
+        return both(result, saved).map(|()| session.failures.report());
+    }
+
+    let result = result.and_then(|()| {
+        if !session.failures.is_empty() {
+            return Ok(());
         }
 
-        apply(
-            theme,
-            scheme,
-            template_name,
-            &template,
-            directives,
-            config,
-            session,
-        )?;
+        let file = session.index.file.clone();
+
+        session.track(&file)?;
+        session.save().map_err(Into::into)
+    });
+
+    if result.is_err() || !session.failures.is_empty() {
+        let failed = session.failures.report();
+
+        // the index is restored along with the files it describes
+        both(result, session.transaction.rollback())?;
+
+        return Ok(failed);
     }
 
-    Ok(())
+    Ok(session.failures.report())
 }
 
-pub(crate) fn all(
-    templates: &Loader,
-    themes: &IndexMap<ThemeName, Theme>,
-    config: &Config,
-    write_mode: WriteMode,
-    dry_run: bool,
-) -> Result<()> {
-    all_internal(templates, themes, config, write_mode, dry_run)
-        .map_err(Error::rendering)
+/// Fails with both errors if both steps failed, so neither gets lost.
+fn both(
+    first: anyhow::Result<()>,
+    second: anyhow::Result<()>,
+) -> anyhow::Result<()> {
+    match (first, second) {
+        (Err(first), Err(second)) => Err(anyhow!("{first:#}; {second:#}")),
+        (first, second) => first.and(second),
+    }
 }
 
-fn all_internal(
+fn render_selection(
     templates: &Loader,
     themes: &IndexMap<ThemeName, Theme>,
+    selection: &Selection,
     config: &Config,
-    write_mode: WriteMode,
-    dry_run: bool,
+    session: &mut Session,
 ) -> anyhow::Result<()> {
-    let mut session =
-        Session::new(templates.providers.clone(), write_mode, dry_run)?;
+    let with_directives: Vec<_> = templates
+        .with_directives()?
+        .into_iter()
+        .filter(|(template_name, _)| {
+            should_render(template_name) && selection.template(template_name)
+        })
+        .collect();
+
+    for (template_name, (template, _)) in &with_directives {
+        warn_unused_swatch(template_name, template);
+    }
+
+    let mut selected = Vec::new();
 
     for theme in themes.values() {
+        if !selection.theme(theme.name.as_str()) {
+            continue;
+        }
+
         for scheme in theme.schemes.values() {
-            all_with(theme, scheme, templates, config, &mut session)?;
+            if !selection.scheme(scheme.name.as_str()) {
+                continue;
+            }
+
+            for (template_name, (template, directives)) in &with_directives {
+                selected.extend(jobs(
+                    theme,
+                    scheme,
+                    template_name,
+                    template,
+                    directives,
+                ));
+            }
         }
     }
 
-    session.save()?;
+    run(&selected, config, session)?;
+
+    // a partial render can't tell orphans apart from unselected or failed
+    // outputs
+    if session.options.prune
+        && selection.is_all()
+        && session.failures.is_empty()
+    {
+        prune::orphans(session)?;
+    }
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+    use crate::config::{self, Location};
+    use crate::templates::library;
+    use crate::themes;
+
+    #[test]
+    fn renders_every_library_template() {
+        let dir = tempfile::tempdir().expect("failed to create temp dir");
+        let root = dir.path();
+        let theme = root.join("themes/cutiepro");
+
+        fs::create_dir_all(&theme).expect("failed to create theme dir");
+        fs::write(
+            root.join(config::FILENAME),
+            "[project]\npolytheme = true\n\n[library]\ntemplates = [\"*\"]\n",
+        )
+        .expect("failed to write config");
+        fs::write(
+            theme.join(config::FILENAME),
+            include_str!("../themes/cutiepro/theymer.toml"),
+        )
+        .expect("failed to write theme config");
+        fs::write(
+            theme.join(themes::BASE_FILENAME),
+            include_str!("../themes/cutiepro/theme.toml"),
+        )
+        .expect("failed to write theme");
+
+        let config = config::load(&Location {
+            config: None,
+            root: Some(root.to_path_buf()),
+        })
+        .unwrap_or_else(|e| panic!("failed to load config: {e}"));
+        let templates = Loader::init(&config)
+            .unwrap_or_else(|e| panic!("failed to load templates: {e}"));
+        let themes = themes::load_all(&config, &mut Failures::default())
+            .unwrap_or_else(|e| panic!("failed to load themes: {e}"));
+        let mut session = Session::in_memory(&config.project.root, Vec::new());
+        let mut rendered = HashSet::new();
+
+        each_job(&templates, &themes, |job| {
+            let (_, formatted, _) =
+                render_formatted(job, &config, &mut session).with_context(
+                    || format!("rendering `{}`", job.template_name),
+                )?;
+
+            assert!(
+                !formatted.contains("\"hex\""),
+                "`{}` renders colors as objects",
+                job.template_name
+            );
+            assert!(
+                !formatted
+                    .lines()
+                    .any(|l| l.trim_end().ends_with("upstream:")),
+                "`{}` has an empty upstream line",
+                job.template_name
+            );
+
+            rendered.insert(job.template_name.to_owned());
+
+            Ok(())
+        })
+        .unwrap_or_else(|e| panic!("{e:#}"));
+
+        for entry in library::LIBRARY {
+            for file in entry.files {
+                assert!(
+                    rendered.contains(file.path),
+                    "`{}` unrendered",
+                    file.path
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn loads_other_themes_when_one_is_broken() {
+        let dir = tempfile::tempdir().expect("failed to create temp dir");
+        let root = dir.path();
+
+        fs::write(root.join(config::FILENAME), "[project]\npolytheme = true\n")
+            .expect("failed to write config");
+
+        for (name, base) in [
+            ("broken", "name = \"broken\"\n[palette\n"),
+            ("cutiepro", include_str!("../themes/cutiepro/theme.toml")),
+        ] {
+            let theme = root.join("themes").join(name);
+
+            fs::create_dir_all(&theme).expect("failed to create theme dir");
+            fs::write(theme.join(config::FILENAME), "inherit = true\n")
+                .expect("failed to write theme config");
+            fs::write(theme.join(themes::BASE_FILENAME), base)
+                .expect("failed to write theme");
+        }
+
+        let config = config::load(&Location {
+            config: None,
+            root: Some(root.to_path_buf()),
+        })
+        .unwrap_or_else(|e| panic!("failed to load config: {e}"));
+        let mut failures = Failures::default();
+        let themes = themes::load_all(&config, &mut failures)
+            .unwrap_or_else(|e| panic!("failed to load themes: {e}"));
+
+        assert_eq!(themes.keys().map(ThemeName::as_str).collect::<Vec<_>>(), [
+            "cutiepro"
+        ]);
+        assert_eq!(failures.report(), 1);
+    }
+
+    #[test]
+    fn rolls_back_when_saving_the_index_fails() {
+        let dir = tempfile::tempdir().expect("failed to create temp dir");
+        let output = dir.path().join("cutiepro.toml");
+
+        // a file where the state dir should be fails the save even as root,
+        // which a read-only dir wouldn't
+        fs::write(dir.path().join(".theymer"), "").expect("failed to block");
+        fs::write(&output, "old").expect("failed to write output");
+
+        let mut session = Session::in_memory(dir.path(), Vec::new());
+
+        session.options.dry_run = false;
+        session.options.transactional = true;
+        session.track(&output).expect("failed to back up output");
+        fs::write(&output, "new").expect("failed to write output");
+
+        conclude(&mut session, Ok(())).expect_err("saving should fail");
+        assert_eq!(
+            fs::read_to_string(&output).expect("failed to read output"),
+            "old"
+        );
+    }
+}
@@ -0,0 +1,46 @@
+use indexmap::IndexMap;
+
+use super::{Failures, Session, each_job, render};
+use crate::output::events;
+use crate::templates::Loader;
+use crate::{Config, Error, Result, Theme, ThemeName};
+
+
+pub(crate) fn all(
+    templates: &Loader,
+    themes: &IndexMap<ThemeName, Theme>,
+    config: &Config,
+    failures: Failures,
+) -> Result<()> {
+    let mut session =
+        Session::in_memory(&config.project.root, templates.providers.clone());
+
+    // broken theme files count as checked so the total covers every failure
+    let mut total = failures.loads();
+
+    session.failures = failures;
+
+    each_job(templates, themes, |job| {
+        total += 1;
+
+        if let Err(e) = render(job, config, &mut session) {
+            session.failures.push(job, &e);
+        }
+
+        Ok(())
+    })
+    .map_err(Error::rendering)?;
+
+    let failed = session.failures.report();
+
+    if failed > 0 {
+        return Err(Error::Check { failed, total });
+    }
+
+    events::print(&format!(
+        "checked {total} outputs across {} theme(s): no errors",
+        themes.len()
+    ));
+
+    Ok(())
+}
@@ -0,0 +1,111 @@
+use std::fs;
+use std::io::{self, IsTerminal as _, Write as _};
+use std::path::Path;
+
+use anyhow::Context as _;
+use log::info;
+
+use super::{Dependencies, Index, Job, Session, print_diff};
+use crate::manifest::store;
+
+const PROMPT: &str = "[o]verwrite, [k]eep, [d]iff, [a]dopt as new baseline \
+                      (uppercase applies to all remaining conflicts)";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Resolution {
+    Overwrite,
+    Keep,
+    Adopt,
+}
+
+pub(super) fn resolve(
+    path: &Path,
+    formatted: &str,
+    session: &mut Session,
+) -> anyhow::Result<Option<Resolution>> {
+    if !session.options.interactive
+        || !io::stdin().is_terminal()
+        || !io::stderr().is_terminal()
+    {
+        return Ok(None);
+    }
+
+    if let Some(resolution) = session.resolve_all {
+        return Ok(Some(resolution));
+    }
+
+    loop {
+        eprint!(
+            "conflict: `{}` was modified since it was last \
+             rendered\n{PROMPT}? ",
+            path.display()
+        );
+        io::stderr().flush().context("flushing conflict prompt")?;
+
+        let mut answer = String::new();
+
+        if io::stdin()
+            .read_line(&mut answer)
+            .context("reading conflict resolution")?
+            == 0
+        {
+            session.resolve_all = Some(Resolution::Keep);
+
+            return Ok(Some(Resolution::Keep));
+        }
+
+        let answer = answer.trim();
+
+        let resolution = match answer.to_lowercase().as_str() {
+            "o" | "overwrite" => Resolution::Overwrite,
+            "k" | "keep" => Resolution::Keep,
+            "a" | "adopt" => Resolution::Adopt,
+            "d" | "diff" => {
+                print_diff(path, formatted)?;
+
+                continue;
+            }
+            _ => continue,
+        };
+
+        if answer.chars().next().is_some_and(char::is_uppercase) {
+            session.resolve_all = Some(resolution);
+        }
+
+        return Ok(Some(resolution));
+    }
+}
+
+pub(super) fn adopt(
+    path: &Path,
+    dependencies: &Dependencies,
+    job: &Job<'_>,
+    session: &mut Session,
+) -> anyhow::Result<()> {
+    if session.options.dry_run {
+        info!("would adopt `{}` as new baseline", path.display());
+
+        return Ok(());
+    }
+
+    let current = fs::read_to_string(path).with_context(|| {
+        format!("reading file `{}` for hashing", path.display())
+    })?;
+
+    let entry = Index::create_entry(
+        path,
+        job.theme,
+        job.scheme,
+        job.template,
+        &current,
+        dependencies,
+    );
+
+    store::put(&session.root, &current)?;
+
+    session.index.insert(entry);
+
+    info!("adopted `{}` as new baseline", path.display());
+
+    Ok(())
+}
@@ -1,14 +1,105 @@
-use std::collections::BTreeMap;
+use std::collections::{BTreeMap, BTreeSet};
 use std::sync::Arc;
 
 use indexmap::IndexMap;
+use minijinja::value::Enumerator;
 
 use super::Color;
-use crate::Result;
+use super::objects::{Group, Reads};
 use crate::output::{Special, Style, TextStyle};
 use crate::themes::{
     Meta, ResolvedExtra, ResolvedRole, RoleName, Scheme, Swatch, Theme,
 };
+use crate::{Result, manifest};
+
+
+/// What a template sees when it renders, recording which values it reads.
+#[derive(Debug, Clone)]
+pub(crate) struct Context {
+    values: BTreeMap<String, minijinja::Value>,
+    reads: Reads,
+}
+
+impl Context {
+    pub(crate) fn contains_key(&self, key: &str) -> bool {
+        self.values.contains_key(key)
+    }
+
+    pub(crate) fn to_value(&self) -> minijinja::Value {
+        minijinja::Value::from_object(self.clone())
+    }
+
+    /// The names read since the last call.
+    pub(crate) fn take_reads(&self) -> BTreeSet<String> {
+        self.reads.take()
+    }
+
+    /// Hashes the current values of `names`, so a template only goes stale
+    /// when something it actually read changes.
+    pub(crate) fn fingerprint(
+        &self,
+        names: &BTreeSet<String>,
+    ) -> anyhow::Result<String> {
+        let content = names
+            .iter()
+            .map(|name| {
+                let value = match name.split_once('.') {
+                    Some((group, role)) => self
+                        .group(group)
+                        .and_then(|group| group.get(role))
+                        .map(serde_json::to_string),
+                    None => self.values.get(name).map(|value| {
+                        value.downcast_object_ref::<Group>().map_or_else(
+                            || serde_json::to_string(value),
+                            |group| serde_json::to_string(group.roles()),
+                        )
+                    }),
+                }
+                .transpose()?;
+
+                Ok(format!(
+                    "{name} = {}\n",
+                    value.as_deref().unwrap_or("undefined")
+                ))
+            })
+            .collect::<anyhow::Result<String>>()?;
+
+        Ok(manifest::hash(&content))
+    }
+
+    fn group(&self, name: &str) -> Option<&Group> {
+        self.values.get(name)?.downcast_object_ref::<Group>()
+    }
+}
+
+impl minijinja::value::Object for Context {
+    fn get_value(
+        self: &Arc<Self>,
+        key: &minijinja::Value,
+    ) -> Option<minijinja::Value> {
+        let name = key.as_str()?;
+
+        // groups record which of their roles are read instead
+        if self.group(name).is_none() {
+            self.reads.record(name.to_owned());
+        }
+
+        self.values.get(name).cloned()
+    }
+
+    fn enumerate(self: &Arc<Self>) -> Enumerator {
+        for name in self.values.keys() {
+            self.reads.record(name.clone());
+        }
+
+        Enumerator::Values(
+            self.values
+                .keys()
+                .map(|name| minijinja::Value::from(name.as_str()))
+                .collect(),
+        )
+    }
+}
 
 
 pub(crate) fn build(
@@ -17,8 +108,9 @@ pub(crate) fn build(
     special: &Special,
     style: &Arc<Style>,
     current_swatch: Option<&str>,
-) -> Result<BTreeMap<String, minijinja::Value>> {
+) -> Result<Context> {
     let mut ctx = BTreeMap::new();
+    let reads = Reads::default();
 
     let mut groups: BTreeMap<String, BTreeMap<String, minijinja::Value>> =
         BTreeMap::new();
@@ -41,7 +133,9 @@ pub(crate) fn build(
     }
 
     for (group_name, group_map) in groups {
-        ctx.insert(group_name, minijinja::Value::from(group_map));
+        let group = Group::new(group_name.clone(), group_map, reads.clone());
+
+        ctx.insert(group_name, minijinja::Value::from_object(group));
     }
 
     if let Some(resolved_extra) = &scheme.resolved_extra {
@@ -56,7 +150,7 @@ pub(crate) fn build(
 
     insert_set_test_roles(&mut ctx, scheme);
 
-    Ok(ctx)
+    Ok(Context { values: ctx, reads })
 }
 
 fn insert_meta(
@@ -121,7 +215,9 @@ fn ascii_fallback(
         .or_else(|| unicode.map(|s| deunicode::deunicode(s)))
 }
 
-fn map_swatches_to_roles(scheme: &Scheme) -> IndexMap<String, Vec<String>> {
+pub(super) fn map_swatches_to_roles(
+    scheme: &Scheme,
+) -> IndexMap<String, Vec<String>> {
     let mut map: IndexMap<String, Vec<String>> = IndexMap::new();
 
     for swatch in &scheme.palette {
@@ -326,3 +422,58 @@ fn insert_special(
 
     ctx.insert("special".to_owned(), minijinja::Value::from(special_map));
 }
+
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::{assert_eq, assert_ne};
+
+    use super::*;
+
+    fn context(comment: &str, keyword: &str) -> Context {
+        let reads = Reads::default();
+        let roles = BTreeMap::from([
+            ("comment".to_owned(), minijinja::Value::from(comment)),
+            ("keyword".to_owned(), minijinja::Value::from(keyword)),
+        ]);
+        let group = Group::new("syntax".to_owned(), roles, reads.clone());
+
+        Context {
+            values: BTreeMap::from([
+                ("syntax".to_owned(), minijinja::Value::from_object(group)),
+                ("theme".to_owned(), minijinja::Value::from("cutiepro")),
+            ]),
+            reads,
+        }
+    }
+
+    #[test]
+    fn only_depends_on_roles_read() {
+        let mut env = minijinja::Environment::new();
+        env.add_template("theme", "{{ theme }}: {{ syntax.comment }}")
+            .unwrap_or_else(|e| panic!("failed to add template: {e}"));
+
+        let ctx = context("#7a6e6b", "#ff6b9a");
+        let rendered = env
+            .get_template("theme")
+            .and_then(|template| template.render(ctx.to_value()))
+            .unwrap_or_else(|e| panic!("failed to render: {e}"));
+
+        assert_eq!(rendered, "cutiepro: #7a6e6b");
+
+        let names = ctx.take_reads();
+
+        assert_eq!(names.iter().map(String::as_str).collect::<Vec<_>>(), [
+            "syntax.comment",
+            "theme"
+        ]);
+
+        let hash = |ctx: &Context| {
+            ctx.fingerprint(&names)
+                .unwrap_or_else(|e| panic!("failed to fingerprint: {e}"))
+        };
+
+        assert_eq!(hash(&ctx), hash(&context("#7a6e6b", "#000000")));
+        assert_ne!(hash(&ctx), hash(&context("#000000", "#ff6b9a")));
+    }
+}
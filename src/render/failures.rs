@@ -0,0 +1,92 @@
+use std::mem;
+use std::path::{Path, PathBuf};
+
+use indexmap::IndexMap;
+use itertools::Itertools as _;
+use log::error;
+
+use super::Job;
+use crate::output::{Event, events};
+
+
+/// Outputs that failed to render, grouped by template and cause so the same
+/// mistake across many schemes is only reported once, and theme or scheme
+/// files that failed to load.
+#[derive(Debug, Default)]
+pub(crate) struct Failures {
+    renders: IndexMap<(String, String), Vec<String>>,
+    loads: IndexMap<PathBuf, String>,
+}
+
+impl Failures {
+    pub(super) fn push(&mut self, job: &Job<'_>, error: &anyhow::Error) {
+        let mut output =
+            format!("{}/{}", job.theme.name.as_str(), job.scheme.name.as_str());
+
+        if let Some(swatch) = job.swatch {
+            output.push('/');
+            output.push_str(swatch);
+        }
+
+        self.renders
+            .entry((
+                job.template_name.to_owned(),
+                error.root_cause().to_string(),
+            ))
+            .or_default()
+            .push(output);
+    }
+
+    /// Records that the theme or scheme at `path` couldn't be loaded, so
+    /// the rest can still render.
+    pub(crate) fn push_load(&mut self, path: &Path, error: &crate::Error) {
+        self.loads.insert(path.to_path_buf(), error.to_string());
+    }
+
+    /// Logs every failed load and group of failures, then forgets them.
+    /// Returns how many files and outputs failed.
+    pub(crate) fn report(&mut self) -> usize {
+        let mut failed = 0;
+
+        for (path, cause) in mem::take(&mut self.loads) {
+            failed += 1;
+
+            let message =
+                format!("failed to load `{}`: {cause}", path.display());
+
+            error!("{message}");
+            events::emit(&Event::Error { message: &message });
+        }
+
+        for ((template, cause), outputs) in mem::take(&mut self.renders) {
+            failed += outputs.len();
+
+            let message = match outputs.as_slice() {
+                [output] => {
+                    format!(
+                        "template `{template}` fails for `{output}`: {cause}"
+                    )
+                }
+                _ => format!(
+                    "template `{template}` fails for {} outputs ({}): {cause}",
+                    outputs.len(),
+                    outputs.iter().map(|o| format!("`{o}`")).join(", ")
+                ),
+            };
+
+            error!("{message}");
+            events::emit(&Event::Error { message: &message });
+        }
+
+        failed
+    }
+
+    /// How many theme or scheme files failed to load.
+    pub(super) fn loads(&self) -> usize {
+        self.loads.len()
+    }
+
+    pub(super) fn is_empty(&self) -> bool {
+        self.renders.is_empty() && self.loads.is_empty()
+    }
+}
@@ -1,31 +1,43 @@
+use std::collections::BTreeSet;
 use std::path::{Path, PathBuf};
 
+use indexmap::IndexSet;
+use itertools::Itertools as _;
 use serde::{Deserialize, Serialize};
 
-use crate::output::FileStatus;
+use super::context::Context;
+use crate::config::Provider;
+use crate::output::{FileStatus, Style};
+use crate::templates::Directives;
 use crate::{
-    Manifest, ManifestEntry, Scheme, SchemeName, Theme, ThemeName, manifest,
+    Config, Manifest, ManifestEntry, ProjectType, Scheme, SchemeName, Theme,
+    ThemeName, manifest,
 };
 
 
-pub(super) type Index = Manifest<Entry>;
+pub(crate) type Index = Manifest<Entry>;
 
 impl Index {
     pub(crate) fn check(
         &self,
         path: &Path,
-        theme: &Theme,
-        scheme: &Scheme,
         template: &minijinja::Template<'_, '_>,
+        context: &Context,
+        settings: &str,
     ) -> anyhow::Result<FileStatus> {
         let Some(entry) = self.get(path) else {
             return Ok(FileStatus::NotTracked);
         };
 
         manifest::check_status(path, &entry.render_hash, || {
-            Ok(hash_theme(theme)? != entry.theme_hash
-                || hash_scheme(scheme)? != entry.scheme_hash
-                || hash_template(template) != entry.template_hash)
+            Ok(hash_template(template) != entry.template_hash
+                || settings != entry.settings_hash
+                || hash_dependencies(
+                    template,
+                    context,
+                    &entry.dependencies,
+                    &entry.partials,
+                )? != entry.dependency_hash)
         })
     }
 
@@ -35,21 +47,50 @@ impl Index {
         scheme: &Scheme,
         template: &minijinja::Template<'_, '_>,
         content: &str,
-    ) -> anyhow::Result<Entry> {
-        Ok(Entry {
+        dependencies: &Dependencies,
+    ) -> Entry {
+        Entry {
             path: path.to_path_buf(),
             template: template.name().to_owned(),
             theme: theme.name.clone(),
             scheme: scheme.name.clone(),
             render_hash: manifest::hash(content),
             template_hash: hash_template(template),
-            theme_hash: hash_theme(theme)?,
-            scheme_hash: hash_scheme(scheme)?,
-        })
+            dependencies: dependencies.names.clone(),
+            partials: dependencies.partials.clone(),
+            dependency_hash: dependencies.hash.clone(),
+            settings_hash: dependencies.settings.clone(),
+        }
     }
 }
 
 
+/// The context values and partials a template read while rendering an
+/// output, and a hash of what they were.
+#[derive(Debug, Clone)]
+pub(crate) struct Dependencies {
+    pub names: BTreeSet<String>,
+    pub partials: BTreeSet<String>,
+    pub hash: String,
+
+    /// From [`hash_settings`].
+    pub settings: String,
+}
+
+/// Everything besides the template source and its context that changes what
+/// an output renders to.
+#[derive(Debug, Serialize)]
+struct Settings<'a> {
+    strip_directives: &'a [Vec<String>],
+    providers: &'a [Provider],
+    project_type: ProjectType,
+    render_all_into: Option<&'a Path>,
+    style: &'a Style,
+    source: Option<&'a str>,
+    passthrough: &'a IndexSet<String>,
+}
+
+
 #[non_exhaustive]
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub(crate) struct Entry {
@@ -58,14 +99,26 @@ pub(crate) struct Entry {
     pub scheme: SchemeName,
     pub template: String,
     pub render_hash: String,
-    pub theme_hash: String,
-    pub scheme_hash: String,
     pub template_hash: String,
+
+    /// Entries written before dependencies were tracked have none, and their
+    /// empty hash never matches, so they're rendered once more.
+    #[serde(default)]
+    pub dependencies: BTreeSet<String>,
+    #[serde(default)]
+    pub partials: BTreeSet<String>,
+    #[serde(default)]
+    pub dependency_hash: String,
+
+    /// Covers the config settings and directives the output was rendered
+    /// with. Missing before version 2, and empty never matches.
+    #[serde(default)]
+    pub settings_hash: String,
 }
 
 impl ManifestEntry for Entry {
     const FILENAME: &'static str = "index.json";
-    const VERSION: u8 = 0;
+    const VERSION: u8 = 2;
 
     fn path(&self) -> &Path {
         &self.path
@@ -74,23 +127,150 @@ impl ManifestEntry for Entry {
     fn hash(&self) -> &str {
         &self.render_hash
     }
+
+    fn migrate(&mut self, version: u8) {
+        // dependencies weren't recorded before version 1, so there's no
+        // telling whether they changed; an empty hash never matches, which
+        // updates the output once
+        if version < 1 {
+            self.dependency_hash.clear();
+        }
+    }
 }
 
 
-fn hash_theme(theme: &Theme) -> anyhow::Result<String> {
-    let json = serde_json::to_string_pretty(theme)?;
+fn hash_template(template: &minijinja::Template<'_, '_>) -> String {
+    manifest::hash(template.source())
+}
 
-    Ok(manifest::hash(&json))
+/// Hashes the values of `names` in `context` along with the source of every
+/// partial in `partials`, looked up next to `template`.
+pub(crate) fn hash_dependencies(
+    template: &minijinja::Template<'_, '_>,
+    context: &Context,
+    names: &BTreeSet<String>,
+    partials: &BTreeSet<String>,
+) -> anyhow::Result<String> {
+    Ok(manifest::hash(&format!(
+        "{}\n{}",
+        context.fingerprint(names)?,
+        hash_partials(template, partials)
+    )))
 }
 
+fn hash_partials(
+    template: &minijinja::Template<'_, '_>,
+    partials: &BTreeSet<String>,
+) -> String {
+    let env = template.new_state().env();
 
-fn hash_scheme(scheme: &Scheme) -> anyhow::Result<String> {
-    let json = serde_json::to_string_pretty(scheme)?;
+    partials
+        .iter()
+        .map(|name| {
+            // a partial that's gone fails the render, which needs rendering
+            // to find out
+            let hash = env
+                .get_template(name)
+                .map_or_else(|_| "missing".to_owned(), |t| hash_template(&t));
 
-    Ok(manifest::hash(&json))
+            format!("{name} = {hash}")
+        })
+        .join("\n")
 }
 
 
-fn hash_template(template: &minijinja::Template<'_, '_>) -> String {
-    manifest::hash(template.source())
+pub(crate) fn hash_settings(
+    config: &Config,
+    directives: &Directives,
+) -> anyhow::Result<String> {
+    let settings = Settings {
+        strip_directives: &config.strip_directives,
+        providers: &config.providers,
+        project_type: config.project.r#type,
+        render_all_into: config.project.render_all_into.as_deref(),
+        style: &directives.style,
+        source: directives.source.as_deref(),
+        passthrough: &directives.passthrough,
+    };
+
+    Ok(manifest::hash(&serde_json::to_string(&settings)?))
+}
+
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use indoc::indoc;
+    use pretty_assertions::{assert_eq, assert_ne};
+
+    use super::*;
+    use crate::templates;
+
+    #[test]
+    fn migrates_version_0_entries() {
+        let dir = tempfile::tempdir().expect("failed to create temp dir");
+        let state = dir.path().join(".theymer");
+
+        fs::create_dir_all(&state).expect("failed to create state dir");
+        fs::write(state.join(Entry::FILENAME), indoc! {r#"
+                {
+                  "version": 0,
+                  "entries": [
+                    {
+                      "path": "cutiepro.toml",
+                      "theme": "cutiepro",
+                      "scheme": "cutiepro",
+                      "template": "THEME.toml.jinja",
+                      "render_hash": "sha256:0",
+                      "theme_hash": "sha256:1",
+                      "scheme_hash": "sha256:2",
+                      "template_hash": "sha256:3"
+                    }
+                  ]
+                }
+            "#})
+        .expect("failed to write index");
+
+        let index = Index::load_or_create(dir.path())
+            .unwrap_or_else(|e| panic!("failed to load index: {e}"));
+        let entry = index
+            .get(Path::new("cutiepro.toml"))
+            .expect("entry should survive migration");
+
+        assert_eq!(index.version, Entry::VERSION);
+        assert_eq!(entry.dependency_hash, "");
+        assert_eq!(entry.settings_hash, "");
+        assert_eq!(entry.template_hash, "sha256:3");
+    }
+
+    #[test]
+    fn editing_a_partial_changes_the_hash() {
+        let hash = |partial: &str| {
+            let mut env = minijinja::Environment::new();
+
+            templates::track_loads(&mut env);
+            env.add_template("theme", "{% include '_colors' %}")
+                .and_then(|()| env.add_template("_colors", partial))
+                .unwrap_or_else(|e| panic!("failed to add template: {e}"));
+
+            let template = env
+                .get_template("theme")
+                .unwrap_or_else(|e| panic!("failed to get template: {e}"));
+
+            templates::take_loaded();
+            template
+                .render(())
+                .unwrap_or_else(|e| panic!("failed to render: {e}"));
+
+            let partials = templates::take_loaded();
+
+            assert_eq!(partials, BTreeSet::from(["_colors".to_owned()]));
+
+            hash_partials(&template, &partials)
+        };
+
+        assert_eq!(hash("#7a6e6b"), hash("#7a6e6b"));
+        assert_ne!(hash("#7a6e6b"), hash("#ff6b9a"));
+    }
 }
@@ -0,0 +1,143 @@
+use std::sync::Arc;
+
+use indexmap::IndexMap;
+use minijinja::value::ValueKind;
+use serde_json::{Map as JsonMap, Value as Json};
+
+use super::{Color, Session, build_upstream, context, resolve_path};
+use crate::output::{Special, Style, events};
+use crate::templates::Loader;
+use crate::{Config, Theme, ThemeName, themes};
+
+#[non_exhaustive]
+#[derive(Debug, thiserror::Error)]
+pub(crate) enum Error {
+    #[error("scheme `{scheme}` has no swatch named `{swatch}`")]
+    UnknownSwatch { scheme: String, swatch: String },
+
+    #[error("no template named `{0}`")]
+    UnknownTemplate(String),
+
+    #[error("failed to serialize context as json: {0}")]
+    SerializingJson(#[source] serde_json::Error),
+
+    #[error("failed to serialize context as toml: {0}")]
+    SerializingToml(#[source] toml::ser::Error),
+}
+
+#[non_exhaustive]
+#[derive(Debug, Clone, Copy, Default, clap::ValueEnum)]
+pub(crate) enum Format {
+    #[default]
+    Json,
+    Toml,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct Target<'a> {
+    pub theme: &'a str,
+    pub scheme: Option<&'a str>,
+    pub swatch: Option<&'a str>,
+    pub template: Option<&'a str>,
+}
+
+pub(crate) fn context(
+    templates: &Loader,
+    themes: &IndexMap<ThemeName, Theme>,
+    config: &Config,
+    target: Target<'_>,
+    format: Format,
+) -> crate::Result<()> {
+    let (theme, scheme) =
+        themes::find(themes, Some(target.theme), target.scheme)?;
+
+    if let Some(swatch) = target.swatch
+        && scheme.palette.get(swatch).is_none()
+    {
+        return Err(Error::UnknownSwatch {
+            scheme: scheme.name.to_string(),
+            swatch: swatch.to_owned(),
+        }
+        .into());
+    }
+
+    let (style, special) = if let Some(name) = target.template {
+        let with_directives = templates
+            .with_directives()
+            .map_err(crate::Error::rendering)?;
+        let (_, directives) = with_directives
+            .get(name)
+            .ok_or_else(|| Error::UnknownTemplate(name.to_owned()))?;
+
+        let path = resolve_path(
+            theme,
+            name,
+            scheme.name.as_str(),
+            config,
+            target.swatch,
+        )
+        .map_err(crate::Error::rendering)?;
+        let mut session = Session::in_memory(
+            &config.project.root,
+            templates.providers.clone(),
+        );
+
+        (
+            Arc::clone(&directives.style),
+            build_upstream(scheme.name.as_str(), &path, &mut session, config),
+        )
+    } else {
+        (Arc::new(Style::default()), Special::default())
+    };
+
+    let ctx = context::build(theme, scheme, &special, &style, target.swatch)?;
+    let expanded = expand(&ctx.to_value());
+
+    let output = match format {
+        Format::Json => serde_json::to_string_pretty(&expanded)
+            .map_err(Error::SerializingJson)?,
+        Format::Toml => {
+            toml::to_string_pretty(&expanded).map_err(Error::SerializingToml)?
+        }
+    };
+
+    events::print(output.trim_end());
+
+    Ok(())
+}
+
+/// Converts a context value to plain data, expanding `Color` objects into
+/// every field they expose to templates plus `display`, which is what
+/// `{{ color }}` renders to under the current style.
+fn expand(value: &minijinja::Value) -> Json {
+    if value.downcast_object_ref::<Color>().is_some() {
+        let mut map = JsonMap::new();
+
+        map.insert("display".to_owned(), Json::from(value.to_string()));
+        map.extend(fields(value));
+
+        return Json::Object(map);
+    }
+
+    let kind = value.kind();
+
+    if kind == ValueKind::Map {
+        Json::Object(fields(value).collect())
+    } else if kind == ValueKind::Seq || kind == ValueKind::Iterable {
+        value
+            .try_iter()
+            .map(|items| items.map(|item| expand(&item)).collect())
+            .unwrap_or_default()
+    } else {
+        serde_json::to_value(value).unwrap_or_default()
+    }
+}
+
+fn fields(value: &minijinja::Value) -> impl Iterator<Item = (String, Json)> {
+    value.try_iter().into_iter().flatten().filter_map(|key| {
+        let field = value.get_item(&key).ok()?;
+
+        (!field.is_none() && !field.is_undefined())
+            .then(|| (key.to_string(), expand(&field)))
+    })
+}
@@ -0,0 +1,212 @@
+use std::fs;
+use std::path::Path;
+
+use anyhow::Context as _;
+use log::{debug, info, warn};
+
+use super::{Dependencies, Index, Job, Session};
+use crate::atomic;
+use crate::manifest::store;
+use crate::output::{diff, events};
+
+const CONFLICT_START: &str = "<<<<<<<";
+const CONFLICT_END: &str = ">>>>>>>";
+
+#[derive(Debug)]
+pub(super) enum Outcome {
+    Clean(String),
+
+    /// The merge with conflict markers around the overlapping changes.
+    Conflicted(String),
+    Unavailable,
+}
+
+pub(super) fn three_way(
+    path: &Path,
+    rendered: &str,
+    session: &Session,
+) -> anyhow::Result<Outcome> {
+    let Some(entry) = session.index.get(path) else {
+        return Ok(Outcome::Unavailable);
+    };
+
+    let Some(base) = store::get(&session.root, &entry.render_hash)? else {
+        debug!(
+            "no previous render stored for `{}`; can't merge",
+            path.display()
+        );
+
+        return Ok(Outcome::Unavailable);
+    };
+
+    let current = fs::read_to_string(path).with_context(|| {
+        format!("reading file `{}` for merging", path.display())
+    })?;
+
+    Ok(match diffy::merge(&base, &current, rendered) {
+        Ok(merged) => Outcome::Clean(merged),
+        Err(conflicted) => Outcome::Conflicted(conflicted),
+    })
+}
+
+/// Warns about the changes in `conflicted` that overlap with user edits and
+/// prints each of them with its conflict markers.
+pub(super) fn report(path: &Path, conflicted: &str) {
+    let mut inside = false;
+    let hunks: Vec<_> = conflicted
+        .lines()
+        .filter(|line| {
+            inside |= line.starts_with(CONFLICT_START);
+
+            let keep = inside;
+
+            inside &= !line.starts_with(CONFLICT_END);
+
+            keep
+        })
+        .collect();
+
+    warn!(
+        "`{}` has {} change(s) overlapping with user edits",
+        path.display(),
+        hunks
+            .iter()
+            .filter(|line| line.starts_with(CONFLICT_START))
+            .count()
+    );
+
+    events::print(&hunks.join("\n"));
+}
+
+pub(super) fn write(
+    path: &Path,
+    merged: &str,
+    rendered: &str,
+    dependencies: &Dependencies,
+    job: &Job<'_>,
+    session: &mut Session,
+) -> anyhow::Result<()> {
+    let current = fs::read_to_string(path).with_context(|| {
+        format!("reading file `{}` for merging", path.display())
+    })?;
+
+    if session.options.dry_run {
+        if merged != current {
+            info!("would merge user changes in `{}`", path.display());
+
+            if session.options.diff {
+                events::print(&diff::unified(path, &current, merged));
+            }
+        }
+
+        return Ok(());
+    }
+
+    // the user's edits already cover the new render, but it's still the new
+    // merge base
+    if merged == current {
+        debug!(
+            "kept user changes to `{}` (nothing to merge)",
+            path.display()
+        );
+    } else {
+        session.track(path)?;
+
+        atomic::write(path, merged)
+            .with_context(|| format!("writing file `{}`", path.display()))?;
+
+        info!("merged user changes in `{}`", path.display());
+    }
+
+    store::put(&session.root, rendered)?;
+
+    let entry = Index::create_entry(
+        path,
+        job.theme,
+        job.scheme,
+        job.template,
+        rendered,
+        dependencies,
+    );
+
+    session.index.insert(entry);
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeSet;
+
+    use indoc::indoc;
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+    use crate::render::index;
+    use crate::{SchemeName, ThemeName};
+
+    const BASE: &str = indoc! {r##"
+        [colors]
+        bg = "#181716"
+        fg = "#d5d0c9"
+
+        [cursor]
+        color = "#caa2ca"
+    "##};
+
+    /// Merges `rendered` into `current`, an edited copy of `BASE`.
+    fn merge(current: &str, rendered: &str) -> Outcome {
+        let dir = tempfile::tempdir().expect("failed to create temp dir");
+        let path = dir.path().join("cutiepro.toml");
+        let mut session = Session::in_memory(dir.path(), Vec::new());
+        let render_hash = store::put(dir.path(), BASE)
+            .unwrap_or_else(|e| panic!("failed to store base: {e}"));
+
+        session.index.insert(index::Entry {
+            path: path.clone(),
+            theme: ThemeName::parse("cutiepro")
+                .unwrap_or_else(|e| panic!("invalid theme name: {e}")),
+            scheme: SchemeName::parse("cutiepro")
+                .unwrap_or_else(|e| panic!("invalid scheme name: {e}")),
+            template: "SCHEME.toml.jinja".to_owned(),
+            render_hash,
+            template_hash: String::new(),
+            dependencies: BTreeSet::new(),
+            partials: BTreeSet::new(),
+            dependency_hash: String::new(),
+            settings_hash: String::new(),
+        });
+        fs::write(&path, current).expect("failed to write output");
+
+        three_way(&path, rendered, &session)
+            .unwrap_or_else(|e| panic!("failed to merge: {e}"))
+    }
+
+    #[test]
+    fn merges_user_edits_with_template_changes() {
+        let current = BASE.replace("[cursor]", "# mine\n[cursor]");
+        let rendered = BASE.replace("#181716", "#000000");
+
+        let Outcome::Clean(merged) = merge(&current, &rendered) else {
+            panic!("separate changes should merge cleanly");
+        };
+
+        assert_eq!(merged, current.replace("#181716", "#000000"));
+    }
+
+    #[test]
+    fn marks_conflicting_hunks() {
+        let current = BASE.replace("#caa2ca", "#ffffff");
+        let rendered = BASE.replace("#caa2ca", "#000000");
+
+        let Outcome::Conflicted(conflicted) = merge(&current, &rendered) else {
+            panic!("overlapping changes should conflict");
+        };
+
+        assert!(conflicted.contains(CONFLICT_START));
+        assert!(conflicted.contains("color = \"#ffffff\""));
+        assert!(conflicted.contains("color = \"#000000\""));
+        assert!(conflicted.contains(CONFLICT_END));
+        assert!(conflicted.starts_with("[colors]\nbg = \"#181716\""));
+    }
+}
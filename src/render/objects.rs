@@ -1,11 +1,87 @@
+use std::collections::{BTreeMap, BTreeSet};
 use std::fmt::{Formatter, Result as FmtResult};
-use std::sync::Arc;
+use std::mem;
+use std::sync::{Arc, Mutex, PoisonError};
 
 use minijinja::value::Enumerator;
 use serde::Serialize;
 
 use crate::output::{ColorStyle, Style, TextStyle};
 
+/// Names of the context values a template looked up while rendering, shared
+/// by every object in its context. Roles in a group are recorded as
+/// `group.role`, so reading one doesn't make the template depend on all of
+/// them.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct Reads(Arc<Mutex<BTreeSet<String>>>);
+
+impl Reads {
+    pub(crate) fn record(&self, name: String) {
+        self.0
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .insert(name);
+    }
+
+    /// Everything recorded so far, leaving nothing behind.
+    pub(crate) fn take(&self) -> BTreeSet<String> {
+        mem::take(&mut *self.0.lock().unwrap_or_else(PoisonError::into_inner))
+    }
+}
+
+/// A group of roles like `syntax`, recording which of them are read.
+#[derive(Debug)]
+pub(crate) struct Group {
+    name: String,
+    roles: BTreeMap<String, minijinja::Value>,
+    reads: Reads,
+}
+
+impl Group {
+    pub(crate) const fn new(
+        name: String,
+        roles: BTreeMap<String, minijinja::Value>,
+        reads: Reads,
+    ) -> Self {
+        Self { name, roles, reads }
+    }
+
+    /// Looks up a role without recording it.
+    pub(crate) fn get(&self, role: &str) -> Option<&minijinja::Value> {
+        self.roles.get(role)
+    }
+
+    pub(crate) const fn roles(&self) -> &BTreeMap<String, minijinja::Value> {
+        &self.roles
+    }
+}
+
+impl minijinja::value::Object for Group {
+    fn get_value(
+        self: &Arc<Self>,
+        key: &minijinja::Value,
+    ) -> Option<minijinja::Value> {
+        let role = key.as_str()?;
+
+        // missing roles are recorded too, so defining one later counts as a
+        // change
+        self.reads.record(format!("{}.{role}", self.name));
+
+        self.roles.get(role).cloned()
+    }
+
+    fn enumerate(self: &Arc<Self>) -> Enumerator {
+        self.reads.record(self.name.clone());
+
+        Enumerator::Values(
+            self.roles
+                .keys()
+                .map(|role| minijinja::Value::from(role.as_str()))
+                .collect(),
+        )
+    }
+}
+
 #[derive(Debug, Clone, Serialize)]
 #[serde(untagged)]
 pub(crate) enum Color {
@@ -0,0 +1,101 @@
+use std::num::NonZeroUsize;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Mutex, PoisonError};
+use std::{panic, thread};
+
+
+/// Calls `f` on every item using up to `threads` threads, returning the
+/// results in the order of `items` no matter which thread finished first.
+/// Items not yet started once `cancel` is set are skipped and come back as
+/// `None`.
+pub(super) fn map<T, R, F>(
+    items: Vec<T>,
+    threads: NonZeroUsize,
+    cancel: &AtomicBool,
+    f: F,
+) -> Vec<Option<R>>
+where
+    T: Send,
+    R: Send,
+    F: Fn(T) -> R + Sync,
+{
+    let threads = threads.get().min(items.len());
+    let f = &|item| (!cancel.load(Ordering::Relaxed)).then(|| f(item));
+
+    if threads <= 1 {
+        return items.into_iter().map(f).collect();
+    }
+
+    let queue = &Mutex::new(items.into_iter().enumerate());
+
+    let mut results: Vec<(usize, Option<R>)> = thread::scope(|scope| {
+        // every worker has to be spawned before any is joined
+        let mut workers = Vec::with_capacity(threads);
+
+        for _ in 0..threads {
+            workers.push(scope.spawn(move || {
+                let mut done = Vec::new();
+
+                loop {
+                    let next = queue
+                        .lock()
+                        .unwrap_or_else(PoisonError::into_inner)
+                        .next();
+                    let Some((index, item)) = next else {
+                        break;
+                    };
+
+                    done.push((index, f(item)));
+                }
+
+                done
+            }));
+        }
+
+        workers
+            .into_iter()
+            .flat_map(|worker| {
+                worker.join().unwrap_or_else(|e| panic::resume_unwind(e))
+            })
+            .collect()
+    });
+
+    results.sort_unstable_by_key(|&(index, _)| index);
+
+    results.into_iter().map(|(_, result)| result).collect()
+}
+
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+
+    #[test]
+    fn keeps_item_order() {
+        let items: Vec<usize> = (0..64).collect();
+        let threads = NonZeroUsize::new(4).unwrap_or(NonZeroUsize::MIN);
+
+        assert_eq!(
+            map(items.clone(), threads, &AtomicBool::new(false), |n| n * 2),
+            items.iter().map(|n| Some(n * 2)).collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn skips_items_once_cancelled() {
+        let cancel = AtomicBool::new(false);
+        let threads = NonZeroUsize::new(4).unwrap_or(NonZeroUsize::MIN);
+        let results = map((0..64).collect(), threads, &cancel, |n: usize| {
+            if n == 8 {
+                cancel.store(true, Ordering::Relaxed);
+            }
+
+            n
+        });
+
+        assert_eq!(results[8], Some(8));
+        assert!(results.iter().any(Option::is_none));
+    }
+}
@@ -0,0 +1,68 @@
+use std::fs;
+use std::path::Path;
+
+use anyhow::Context as _;
+use log::{debug, info, warn};
+
+use super::Session;
+use crate::manifest;
+use crate::output::WriteMode;
+
+
+pub(super) fn orphans(session: &mut Session) -> anyhow::Result<()> {
+    for path in session.index.find_orphans(&session.rendered) {
+        let Some(entry) = session.index.get(&path) else {
+            continue;
+        };
+
+        if !path.exists() {
+            debug!(
+                "forgetting `{}` (orphaned, already deleted)",
+                path.display()
+            );
+
+            session.index.remove(&path);
+
+            continue;
+        }
+
+        let user_modified = manifest::hash_file(&path)? != entry.render_hash;
+
+        if user_modified && session.options.write_mode != WriteMode::Force {
+            warn!(
+                "conflict: orphaned `{}` (last modified by user; use \
+                 `--force` to prune)",
+                path.display()
+            );
+
+            continue;
+        }
+
+        if session.options.dry_run {
+            info!("would prune `{}`", path.display());
+        } else {
+            session.track(&path)?;
+
+            fs::remove_file(&path).with_context(|| {
+                format!("pruning orphaned file `{}`", path.display())
+            })?;
+
+            info!("pruned `{}`", path.display());
+
+            remove_if_empty(path.parent());
+        }
+
+        session.index.remove(&path);
+    }
+
+    Ok(())
+}
+
+fn remove_if_empty(dir: Option<&Path>) {
+    if let Some(dir) = dir
+        && fs::read_dir(dir).is_ok_and(|mut entries| entries.next().is_none())
+        && fs::remove_dir(dir).is_ok()
+    {
+        debug!("removed empty directory `{}`", dir.display());
+    }
+}
@@ -0,0 +1,154 @@
+use std::result::Result as StdResult;
+
+use globset::{Glob, GlobSet, GlobSetBuilder};
+
+type Result<T> = StdResult<T, Error>;
+
+#[non_exhaustive]
+#[derive(Debug, thiserror::Error)]
+pub(crate) enum Error {
+    #[error("failed to parse {kind} selector `{pattern}`: {src}")]
+    ParsingGlob {
+        kind: &'static str,
+        pattern: String,
+        src: globset::Error,
+    },
+
+    #[error("failed to build {kind} selectors: {src}")]
+    Building {
+        kind: &'static str,
+        src: globset::Error,
+    },
+}
+
+/// Themes, schemes and templates picked on the command line. Each unset
+/// selector matches everything.
+#[derive(Debug, Default)]
+pub(crate) struct Selection {
+    themes: Option<GlobSet>,
+    schemes: Option<GlobSet>,
+    templates: Option<GlobSet>,
+}
+
+impl Selection {
+    pub(crate) fn new(
+        themes: &[String],
+        schemes: &[String],
+        templates: &[String],
+    ) -> Result<Self> {
+        Ok(Self {
+            themes: build("theme", themes)?,
+            schemes: build("scheme", schemes)?,
+            templates: build("template", templates)?,
+        })
+    }
+
+    pub(crate) const fn is_all(&self) -> bool {
+        self.themes.is_none()
+            && self.schemes.is_none()
+            && self.templates.is_none()
+    }
+
+    pub(crate) fn theme(&self, name: &str) -> bool {
+        self.themes.as_ref().is_none_or(|set| set.is_match(name))
+    }
+
+    pub(crate) fn scheme(&self, name: &str) -> bool {
+        self.schemes.as_ref().is_none_or(|set| set.is_match(name))
+    }
+
+    /// Matches against the full template path as well as each of its
+    /// components, so `helix` selects `ports/helix/SCHEME.toml.jinja`.
+    pub(crate) fn template(&self, name: &str) -> bool {
+        self.templates.as_ref().is_none_or(|set| {
+            set.is_match(name) || name.split('/').any(|part| set.is_match(part))
+        })
+    }
+}
+
+fn build(kind: &'static str, patterns: &[String]) -> Result<Option<GlobSet>> {
+    if patterns.is_empty() {
+        return Ok(None);
+    }
+
+    let mut builder = GlobSetBuilder::new();
+
+    for pattern in patterns {
+        builder.add(Glob::new(pattern).map_err(|src| Error::ParsingGlob {
+            kind,
+            pattern: pattern.clone(),
+            src,
+        })?);
+    }
+
+    builder
+        .build()
+        .map(Some)
+        .map_err(|src| Error::Building { kind, src })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn selection(
+        themes: &[&str],
+        schemes: &[&str],
+        templates: &[&str],
+    ) -> Selection {
+        let owned = |patterns: &[&str]| {
+            patterns.iter().map(ToString::to_string).collect::<Vec<_>>()
+        };
+
+        Selection::new(&owned(themes), &owned(schemes), &owned(templates))
+            .unwrap_or_else(|e| panic!("failed to build selection: {e}"))
+    }
+
+    #[test]
+    fn matches_everything_without_selectors() {
+        let selection = selection(&[], &[], &[]);
+
+        assert!(selection.is_all());
+        assert!(selection.theme("cutiepro"));
+        assert!(selection.scheme("cutiepro"));
+        assert!(selection.template("ports/helix/SCHEME.toml.jinja"));
+    }
+
+    #[test]
+    fn selects_themes() {
+        let selection = selection(&["cutie*"], &[], &[]);
+
+        assert!(!selection.is_all());
+        assert!(selection.theme("cutiepro"));
+        assert!(!selection.theme("catppuccin"));
+        assert!(selection.scheme("latte"));
+    }
+
+    #[test]
+    fn selects_schemes() {
+        let selection = selection(&[], &["*-dark", "latte"], &[]);
+
+        assert!(selection.scheme("cutiepro-dark"));
+        assert!(selection.scheme("latte"));
+        assert!(!selection.scheme("cutiepro-light"));
+        assert!(selection.theme("catppuccin"));
+    }
+
+    #[test]
+    fn selects_templates_by_path_or_component() {
+        let selection = selection(&[], &[], &["helix", "*.json.jinja"]);
+
+        assert!(selection.template("ports/helix/SCHEME.toml.jinja"));
+        assert!(selection.template("vscode/THEME.json.jinja"));
+        assert!(!selection.template("ports/kitty/SCHEME.conf.jinja"));
+        assert!(!selection.template("helixish/SCHEME.toml.jinja"));
+    }
+
+    #[test]
+    fn rejects_malformed_selectors() {
+        let error = Selection::new(&[], &["[".to_owned()], &[])
+            .expect_err("malformed glob should fail");
+
+        assert!(matches!(error, Error::ParsingGlob { kind: "scheme", .. }));
+    }
+}
@@ -0,0 +1,306 @@
+use std::path::{Path, PathBuf};
+use std::{fs, io};
+
+use indexmap::IndexMap;
+use itertools::Itertools as _;
+use log::info;
+use regex::{Captures, Regex};
+
+use super::context;
+use crate::output::events;
+use crate::templates::JINJA_TEMPLATE_SUFFIX;
+use crate::themes::RoleValue;
+use crate::{Scheme, Theme, ThemeName, atomic, themes};
+
+#[non_exhaustive]
+#[derive(Debug, thiserror::Error)]
+pub(crate) enum Error {
+    #[error("failed to read `{path}`: {src}")]
+    Reading { path: String, src: io::Error },
+
+    #[error("failed to write `{path}`: {src}")]
+    Writing { path: String, src: io::Error },
+
+    #[error("`{0}` already exists (pick another path with `--output`)")]
+    OutputExists(String),
+}
+
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct Target<'a> {
+    pub file: &'a Path,
+    pub theme: Option<&'a str>,
+    pub scheme: &'a str,
+    pub output: Option<&'a Path>,
+}
+
+/// What a palette color gets replaced with, plus the other candidates that
+/// would have fit just as well.
+#[derive(Debug, Clone)]
+struct Replacement {
+    expr: String,
+    alternatives: Vec<String>,
+    lines: Vec<usize>,
+}
+
+/// Replaces every color literal in `target.file` that matches a swatch in
+/// the scheme with a placeholder for the role using it, and writes the
+/// result as a new template.
+pub(crate) fn file(
+    themes: &IndexMap<ThemeName, Theme>,
+    target: Target<'_>,
+) -> crate::Result<()> {
+    let (_, scheme) = themes::find(themes, target.theme, Some(target.scheme))?;
+
+    let content =
+        fs::read_to_string(target.file).map_err(|src| Error::Reading {
+            path: target.file.display().to_string(),
+            src,
+        })?;
+
+    #[expect(clippy::expect_used, reason = "the pattern is a literal")]
+    let literals = Regex::new(r"(#|0[xX])([0-9a-fA-F]{6})([0-9a-fA-F]{2})?\b")
+        .expect("regex should be valid");
+    let mut replacements = candidates(scheme);
+    let mut unmatched: IndexMap<String, Vec<usize>> = IndexMap::new();
+
+    let template = escape_jinja(&content)
+        .split_inclusive('\n')
+        .enumerate()
+        .map(|(i, line)| {
+            literals.replace_all(line, |caps: &Captures<'_>| {
+                let literal = &caps[0];
+                let hex = format!("#{}", caps[2].to_lowercase());
+
+                let Some(replacement) = replacements.get_mut(&hex) else {
+                    unmatched
+                        .entry(literal.to_owned())
+                        .or_default()
+                        .push(i + 1);
+
+                    return literal.to_owned();
+                };
+
+                replacement.lines.push(i + 1);
+
+                placeholder(&replacement.expr, caps)
+            })
+        })
+        .collect::<String>();
+
+    let output = target.output.map_or_else(
+        || {
+            let mut path = target.file.as_os_str().to_owned();
+            path.push(JINJA_TEMPLATE_SUFFIX);
+
+            PathBuf::from(path)
+        },
+        Path::to_path_buf,
+    );
+
+    if output.exists() {
+        return Err(Error::OutputExists(output.display().to_string()).into());
+    }
+
+    atomic::write(&output, template).map_err(|src| Error::Writing {
+        path: output.display().to_string(),
+        src,
+    })?;
+
+    report(&replacements, &unmatched);
+
+    info!(
+        "templatized `{}` with `{}` into `{}`",
+        target.file.display(),
+        scheme.name,
+        output.display()
+    );
+
+    Ok(())
+}
+
+/// Picks a placeholder for every hex value in the palette. Roles that point
+/// straight at a swatch win over roles referencing other roles, which win
+/// over unset roles falling back to their base; colors no role uses fall
+/// back to a swatch lookup.
+fn candidates(scheme: &Scheme) -> IndexMap<String, Replacement> {
+    let swatch_roles = context::map_swatches_to_roles(scheme);
+    let mut by_hex: IndexMap<String, Vec<(u8, String)>> = IndexMap::new();
+
+    for swatch in &scheme.palette {
+        let name = swatch.name.to_string();
+        let exprs = by_hex.entry(swatch.hex().to_string()).or_default();
+        let roles = swatch_roles.get(&name).into_iter().flatten();
+
+        exprs.extend(roles.filter_map(|role| {
+            let rank = match scheme.roles.get(&role.parse().ok()?) {
+                Some(RoleValue::Swatch(_)) => 0,
+                Some(RoleValue::Role(_)) => 1,
+                None => 2,
+            };
+
+            Some((rank, role.clone()))
+        }));
+        exprs.push((
+            u8::MAX,
+            format!(
+                "(palette | selectattr(\"name\", \"eq\", {name:?}) | first)"
+            ),
+        ));
+    }
+
+    by_hex
+        .into_iter()
+        .map(|(hex, mut exprs)| {
+            exprs.sort_by_key(|(rank, _)| *rank);
+
+            let best = exprs.first().map(|(rank, _)| *rank);
+            let mut tied = exprs
+                .into_iter()
+                .take_while(|(rank, _)| Some(*rank) == best)
+                .map(|(_, expr)| expr);
+
+            let expr = tied.next().unwrap_or_default();
+
+            (hex, Replacement {
+                expr,
+                alternatives: tied.collect(),
+                lines: Vec::new(),
+            })
+        })
+        .collect()
+}
+
+/// Keeps the literal's prefix, case and alpha channel around the hex value
+/// of `expr`.
+fn placeholder(expr: &str, caps: &Captures<'_>) -> String {
+    let prefixed = caps[1].eq_ignore_ascii_case("0x");
+    let upper = caps[2].chars().any(|c| c.is_ascii_uppercase());
+    let alpha = caps.get(3).map_or("", |m| m.as_str());
+
+    let value = match (prefixed, upper) {
+        (false, false) => format!("{expr}.hex"),
+        (false, true) => format!("{expr}.hex | upper"),
+        (true, false) => format!("{expr}.hex[1:]"),
+        (true, true) => format!("{expr}.hex[1:] | upper"),
+    };
+    let prefix = if prefixed { &caps[1] } else { "" };
+
+    format!("{prefix}{{{{ {value} }}}}{alpha}")
+}
+
+fn report(
+    replacements: &IndexMap<String, Replacement>,
+    unmatched: &IndexMap<String, Vec<usize>>,
+) {
+    let used = replacements.iter().filter(|(_, r)| !r.lines.is_empty());
+
+    for (hex, replacement) in used {
+        events::print(&format!(
+            "{hex} -> {}  (at line {})",
+            replacement.expr,
+            format_lines(&replacement.lines)
+        ));
+
+        if !replacement.alternatives.is_empty() {
+            events::print(&format!(
+                "  ambiguous, could also be {}",
+                replacement
+                    .alternatives
+                    .iter()
+                    .map(|alt| format!("`{alt}`"))
+                    .join(", ")
+            ));
+        }
+    }
+
+    for (literal, lines) in unmatched {
+        events::print(&format!(
+            "{literal} isn't in the palette, left as is  (at line {})",
+            format_lines(lines)
+        ));
+    }
+}
+
+fn format_lines(lines: &[usize]) -> String {
+    lines.iter().dedup().join(", ")
+}
+
+/// Escapes anything in the source that jinja would otherwise try to parse.
+fn escape_jinja(content: &str) -> String {
+    #[expect(clippy::expect_used, reason = "the pattern is a literal")]
+    let re = Regex::new(r"\{[{%#]").expect("regex should be valid");
+
+    re.replace_all(content, "{{ '$0' }}").into_owned()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+    use crate::output::Special;
+    use crate::themes::schemes;
+
+    #[test]
+    fn round_trips_a_rendered_file() {
+        // a swatch no role uses is only reachable through the palette
+        let source = include_str!("../../themes/cutiepro/theme.toml").replacen(
+            "[palette]\n",
+            "[palette]\nunused = \"#123456\"\n",
+            1,
+        );
+        let scheme =
+            schemes::parse("cutiepro", Path::new("theme.toml"), &source)
+                .unwrap_or_else(|e| panic!("failed to parse scheme: {e}"));
+        let name = ThemeName::parse("cutiepro")
+            .unwrap_or_else(|e| panic!("invalid theme name: {e}"));
+        let theme = Theme {
+            name_ascii: name
+                .to_ascii()
+                .unwrap_or_else(|e| panic!("invalid theme name: {e}")),
+            name: name.clone(),
+            schemes: IndexMap::from([(scheme.name.clone(), scheme)]),
+            config: None,
+        };
+        let themes = IndexMap::from([(name, theme)]);
+
+        let dir = tempfile::tempdir().expect("failed to create temp dir");
+        let path = dir.path().join("colors.conf");
+        let rendered = "bg = \"#181716\"\nfg = #D5D0C9\ncursor = \
+                        0xcaa2ca80\nunused = \"#123456\"\n";
+
+        fs::write(&path, rendered).expect("failed to write rendered file");
+        file(&themes, Target {
+            file: &path,
+            theme: None,
+            scheme: "cutiepro",
+            output: None,
+        })
+        .unwrap_or_else(|e| panic!("failed to templatize: {e}"));
+
+        let template = fs::read_to_string(dir.path().join("colors.conf.jinja"))
+            .expect("failed to read template");
+        let (theme, scheme) = themes::find(&themes, None, Some("cutiepro"))
+            .unwrap_or_else(|e| panic!("failed to find scheme: {e}"));
+        let context = context::build(
+            theme,
+            scheme,
+            &Special::default(),
+            &Arc::default(),
+            None,
+        )
+        .unwrap_or_else(|e| panic!("failed to build context: {e}"));
+        let mut env = minijinja::Environment::new();
+
+        env.set_keep_trailing_newline(true);
+
+        let output = env
+            .template_from_str(&template)
+            .and_then(|template| template.render(context.to_value()))
+            .unwrap_or_else(|e| panic!("failed to render:\n{template}\n{e}"));
+
+        assert_eq!(output, rendered);
+    }
+}
@@ -0,0 +1,77 @@
+use std::path::{Path, PathBuf};
+use std::{fs, io, mem};
+
+use anyhow::{Context as _, bail};
+use indexmap::IndexMap;
+use log::{debug, info};
+
+use crate::atomic;
+
+
+/// What every file touched by a transactional run held before it, so the
+/// run can be undone if any output fails.
+#[derive(Debug, Default)]
+pub(crate) struct Transaction {
+    /// `None` for files the run created.
+    backups: IndexMap<PathBuf, Option<Vec<u8>>>,
+}
+
+impl Transaction {
+    /// Backs up `path` unless it was already backed up earlier in the run.
+    pub(super) fn record(&mut self, path: &Path) -> anyhow::Result<()> {
+        if self.backups.contains_key(path) {
+            return Ok(());
+        }
+
+        let previous = match fs::read(path) {
+            Ok(content) => Some(content),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => None,
+            Err(e) => {
+                return Err(e).with_context(|| {
+                    format!("backing up `{}`", path.display())
+                });
+            }
+        };
+
+        self.backups.insert(path.to_path_buf(), previous);
+
+        Ok(())
+    }
+
+    /// Puts every touched file back the way it was, newest change first.
+    /// Keeps going past files that can't be restored and fails with all of
+    /// them at the end.
+    pub(super) fn rollback(&mut self) -> anyhow::Result<()> {
+        let mut restored = 0;
+        let mut failed = Vec::new();
+
+        for (path, previous) in mem::take(&mut self.backups).into_iter().rev() {
+            let result = previous.map_or_else(
+                || match fs::remove_file(&path) {
+                    Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(()),
+                    result => result,
+                },
+                |content| atomic::write(&path, content),
+            );
+
+            match result {
+                Ok(()) => {
+                    restored += 1;
+
+                    debug!("rolled back `{}`", path.display());
+                }
+                Err(e) => failed.push(format!("`{}` ({e})", path.display())),
+            }
+        }
+
+        if restored > 0 {
+            info!("rolled back {restored} file(s)");
+        }
+
+        if !failed.is_empty() {
+            bail!("failed to roll back {}", failed.join(", "));
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,100 @@
+use std::{fs, io};
+
+use anyhow::Context as _;
+use indexmap::IndexMap;
+
+use super::{Failures, Options, Session, each_job, render_formatted};
+use crate::output::{Decision, Event, WriteMode, events, strategy};
+use crate::templates::Loader;
+use crate::{Config, Error, Result, Theme, ThemeName};
+
+
+pub(crate) fn all(
+    templates: &Loader,
+    themes: &IndexMap<ThemeName, Theme>,
+    config: &Config,
+    failures: Failures,
+) -> Result<()> {
+    let mut session = Session::new(
+        &config.project.root,
+        templates.providers.clone(),
+        Options {
+            write_mode: WriteMode::Smart,
+            dry_run: true,
+            diff: false,
+            prune: false,
+            interactive: false,
+            fail_fast: false,
+            transactional: false,
+            jobs: None,
+        },
+    )?;
+
+    session.failures = failures;
+
+    let mut total = 0;
+    let mut stale = 0;
+
+    each_job(templates, themes, |job| {
+        let (path, formatted, status) =
+            render_formatted(job, config, &mut session)?;
+
+        total += 1;
+
+        let current = match fs::read_to_string(&path) {
+            Ok(current) => Some(current),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => None,
+            Err(e) => {
+                return Err(e).with_context(|| {
+                    format!("reading file `{}`", path.display())
+                });
+            }
+        };
+
+        if current.is_some_and(|current| current == formatted) {
+            return Ok(());
+        }
+
+        // the index only tells apart user edits from stale output, whether
+        // the file differs is decided by its content
+        let decision = match strategy::decide(status, WriteMode::Smart) {
+            Decision::Skip => Decision::Update,
+            decision => decision,
+        };
+
+        stale += 1;
+
+        // this is what verify reports, so `-q` mustn't hide it
+        events::print(&format!(
+            "{}: `{}`",
+            decision.log_action(),
+            path.display()
+        ));
+
+        events::emit(&Event::File {
+            decision,
+            path: &path,
+            theme: job.theme.name.as_str(),
+            scheme: job.scheme.name.as_str(),
+            template: job.template_name,
+            dry_run: true,
+        });
+
+        Ok(())
+    })
+    .map_err(Error::rendering)?;
+
+    let failed = session.failures.report();
+
+    if failed > 0 {
+        return Err(Error::Failed { failed });
+    }
+
+    if stale > 0 {
+        return Err(Error::Stale { stale, total });
+    }
+
+    events::print(&format!("all {total} outputs are up to date"));
+
+    Ok(())
+}
@@ -0,0 +1,290 @@
+use std::iter;
+use std::path::{Component, Path};
+use std::sync::mpsc;
+use std::time::Duration;
+
+use indexmap::IndexMap;
+use log::{debug, error, info};
+use notify::event::ModifyKind;
+use notify::{EventKind, RecommendedWatcher, RecursiveMode, Watcher as _};
+
+use super::{
+    Failures, Job, Options, Session, each_job, prune, should_render, write,
+};
+use crate::config::{self, FILENAME as CONFIG_FILENAME, Location};
+use crate::extensions::PathExt as _;
+use crate::templates::Loader;
+use crate::{Config, Error, ProjectType, Result, Theme, ThemeName, themes};
+
+const DEBOUNCE: Duration = Duration::from_millis(200);
+const THEME_FILENAME: &str = "theme.toml";
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Change {
+    Config,
+    Templates,
+    Template(String),
+    Theme(String),
+    Scheme(String, String),
+}
+
+impl Change {
+    fn affects(&self, job: &Job<'_>) -> bool {
+        match self {
+            Self::Config | Self::Templates => true,
+            Self::Template(name) => job.template_name == name,
+            Self::Theme(theme) => job.theme.name.as_str() == theme,
+            Self::Scheme(theme, scheme) => {
+                job.theme.name.as_str() == theme
+                    && job.scheme.name.as_str() == scheme
+            }
+        }
+    }
+}
+
+#[derive(Debug)]
+struct State {
+    config: Config,
+    templates: Loader,
+    themes: IndexMap<ThemeName, Theme>,
+}
+
+pub(crate) fn run(
+    config: Config,
+    templates: Loader,
+    themes: IndexMap<ThemeName, Theme>,
+    options: Options,
+    failures: Failures,
+) -> Result<()> {
+    let (tx, rx) = mpsc::channel();
+    let mut watcher = notify::recommended_watcher(tx)?;
+    let mut session = Session::new(
+        &config.project.root,
+        templates.providers.clone(),
+        options,
+    )?;
+
+    session.failures = failures;
+
+    let mut state = State {
+        config,
+        templates,
+        themes,
+    };
+
+    watch(&mut watcher, &state)?;
+    render(&state, &[Change::Config], &mut session);
+
+    info!(
+        "watching `{}` for changes (press ctrl-c to stop)",
+        state.config.project.root.display()
+    );
+
+    while let Ok(event) = rx.recv() {
+        let mut paths = Vec::new();
+
+        for event in iter::once(event)
+            .chain(iter::from_fn(|| rx.recv_timeout(DEBOUNCE).ok()))
+        {
+            match event {
+                Ok(event) if is_edit(event.kind) => paths.extend(event.paths),
+                Ok(_) => {}
+                Err(e) => error!("watch error: {e}"),
+            }
+        }
+
+        let mut changes = Vec::new();
+
+        for path in paths {
+            if let Some(change) = classify(&path, &state)
+                && !changes.contains(&change)
+            {
+                debug!("`{}` changed", path.display());
+
+                changes.push(change);
+            }
+        }
+
+        if changes.is_empty() {
+            continue;
+        }
+
+        match reload(&mut state, &changes, &mut session.failures)
+            .and_then(|()| watch(&mut watcher, &state))
+        {
+            Ok(()) => {
+                session.providers.clone_from(&state.templates.providers);
+
+                render(&state, &changes, &mut session);
+            }
+            Err(e) => error!("{e}"),
+        }
+    }
+
+    Ok(())
+}
+
+/// Watches what renders read: the config, templates, themes and schemes.
+/// Watching the whole root would also pick up the index and every output.
+fn watch(watcher: &mut RecommendedWatcher, state: &State) -> Result<()> {
+    let config = &state.config;
+    let mut dirs = vec![
+        config.dirs.templates.clone(),
+        config.project.root.join(&config.dirs.themes),
+    ];
+
+    dirs.extend(
+        state
+            .themes
+            .values()
+            .map(|theme| themes::schemes_dir(theme.config.as_ref(), config)),
+    );
+
+    for dir in &dirs {
+        let nested = dirs
+            .iter()
+            .any(|other| other != dir && dir.starts_with(other));
+
+        if dir.is_dir() && !nested {
+            watcher.watch(dir, RecursiveMode::Recursive)?;
+        }
+    }
+
+    // editors often replace files instead of writing to them, which a watch
+    // on the file itself wouldn't survive
+    if let Some(parent) = config.project.config_file.parent() {
+        watcher.watch(parent, RecursiveMode::NonRecursive)?;
+    }
+
+    Ok(())
+}
+
+const fn is_edit(kind: EventKind) -> bool {
+    matches!(
+        kind,
+        EventKind::Create(_)
+            | EventKind::Remove(_)
+            | EventKind::Modify(
+                ModifyKind::Any | ModifyKind::Data(_) | ModifyKind::Name(_)
+            )
+    )
+}
+
+fn classify(path: &Path, state: &State) -> Option<Change> {
+    let config = &state.config;
+
+    if path == config.project.config_file {
+        return Some(Change::Config);
+    }
+
+    if let Ok(name) = path.strip_prefix(&config.dirs.templates) {
+        if !path.is_jinja() {
+            return None;
+        }
+
+        let name = name.to_string_lossy().replace('\\', "/");
+
+        return Some(if should_render(&name) {
+            Change::Template(name)
+        } else {
+            Change::Templates
+        });
+    }
+
+    for theme in state.themes.values() {
+        let theme_dir = config
+            .project
+            .root
+            .join(&config.dirs.themes)
+            .join(theme.name.as_str());
+        let schemes_dir = themes::schemes_dir(theme.config.as_ref(), config);
+
+        if path == theme_dir.join(CONFIG_FILENAME)
+            || path == theme_dir.join(THEME_FILENAME)
+        {
+            return Some(Change::Theme(theme.name.to_string()));
+        }
+
+        if path.parent() == Some(&schemes_dir)
+            && path.has_extension("toml")
+            && let Some(scheme) = path.file_stem()
+        {
+            return Some(Change::Scheme(
+                theme.name.to_string(),
+                scheme.to_string_lossy().into_owned(),
+            ));
+        }
+    }
+
+    // a theme that failed to load isn't known, but fixing it should still
+    // bring it back
+    let themes_dir = config.project.root.join(&config.dirs.themes);
+
+    if config.project.r#type == ProjectType::Polytheme
+        && path.has_extension("toml")
+        && let Ok(rest) = path.strip_prefix(&themes_dir)
+        && let Some(Component::Normal(theme)) = rest.components().next()
+    {
+        return Some(Change::Theme(theme.to_string_lossy().into_owned()));
+    }
+
+    None
+}
+
+fn reload(
+    state: &mut State,
+    changes: &[Change],
+    failures: &mut Failures,
+) -> Result<()> {
+    if changes.contains(&Change::Config) {
+        info!("reloading `{CONFIG_FILENAME}`");
+
+        state.config = config::load(&Location::of(&state.config))?;
+    }
+
+    if changes.iter().any(|c| {
+        matches!(c, Change::Config | Change::Templates | Change::Template(_))
+    }) {
+        state.templates = Loader::init(&state.config)?;
+    }
+
+    if changes.iter().any(|c| {
+        matches!(c, Change::Config | Change::Theme(_) | Change::Scheme(..))
+    }) {
+        state.themes = themes::load_all(&state.config, failures)?;
+    }
+
+    Ok(())
+}
+
+fn render(state: &State, changes: &[Change], session: &mut Session) {
+    let full = changes.contains(&Change::Config);
+
+    if full {
+        session.rendered.clear();
+    }
+
+    let result = each_job(&state.templates, &state.themes, |job| {
+        if changes.iter().any(|change| change.affects(job))
+            && let Err(e) = write(job, &state.config, session)
+        {
+            session.failures.push(job, &e);
+        }
+
+        Ok(())
+    })
+    .map_err(Error::rendering)
+    .and_then(|()| {
+        if full && session.options.prune && session.failures.is_empty() {
+            prune::orphans(session).map_err(Error::rendering)?;
+        }
+
+        session.save()
+    });
+
+    session.failures.report();
+
+    if let Err(e) = result {
+        error!("{e}");
+    }
+}
@@ -0,0 +1,227 @@
+use std::path::Path;
+use std::result::Result as StdResult;
+use std::{env, fs, io};
+
+use indoc::{formatdoc, indoc};
+use log::info;
+
+use crate::config::FILENAME as CONFIG_FILENAME;
+use crate::themes::{self, BASE_FILENAME, RoleKind, all_roles};
+use crate::{Config, ProjectType, SchemeName, ThemeName};
+
+
+const PLACEHOLDER_SWATCH: &str = "placeholder";
+
+
+type Result<T> = StdResult<T, Error>;
+
+#[non_exhaustive]
+#[derive(Debug, thiserror::Error)]
+pub(crate) enum Error {
+    #[error("`{0}` already exists")]
+    Exists(String),
+
+    #[error("theme `{0}` doesn't exist (create it with `they new theme`)")]
+    MissingTheme(String),
+
+    #[error(
+        "monotheme projects only have one theme, named after the project \
+         directory (`{0}`); set `polytheme = true` to add more"
+    )]
+    Monotheme(String),
+
+    #[error("failed to write `{path}`: {src}")]
+    Writing { path: String, src: io::Error },
+}
+
+
+/// Writes a `theymer.toml` into `root`, or the working directory.
+pub(crate) fn init(root: Option<&Path>, polytheme: bool) -> crate::Result<()> {
+    let cwd = env::current_dir()?;
+    let root = root.map_or_else(|| cwd.clone(), |root| cwd.join(root));
+
+    if !polytheme {
+        // monothemes are named after the project directory, so it has to be
+        // usable as a theme name
+        let name = root.file_name().and_then(|n| n.to_str()).unwrap_or("");
+
+        ThemeName::parse(name)?.to_ascii()?;
+    }
+
+    let content = formatdoc! {r#"
+        [project]
+        polytheme = {polytheme}
+        # render_all_into = "render"
+
+        [dirs]
+        # themes = "themes"
+        # schemes = "schemes"
+        # templates = "templates"
+
+        [library]
+        # templates = ["helix", "kitty"]
+    "#};
+
+    create(&root.join(CONFIG_FILENAME), &content)?;
+
+    Ok(())
+}
+
+/// Creates `themes/<name>/` with a `theme.toml` defining every required role
+/// and a `theymer.toml` inheriting the project's settings.
+pub(crate) fn theme(name: &str, config: &Config) -> crate::Result<()> {
+    let name = ThemeName::parse(name)?;
+
+    name.to_ascii()?;
+
+    if config.project.r#type == ProjectType::Monotheme {
+        let project = config
+            .project
+            .root
+            .file_name()
+            .and_then(|n| n.to_str())
+            .unwrap_or_default();
+
+        if name.as_str() != project {
+            return Err(Error::Monotheme(project.to_owned()).into());
+        }
+    }
+
+    let dir = config
+        .project
+        .root
+        .join(&config.dirs.themes)
+        .join(name.as_str());
+    let config_path = dir.join(CONFIG_FILENAME);
+    let base_path = dir.join(BASE_FILENAME);
+
+    for path in [&config_path, &base_path] {
+        if path.exists() {
+            return Err(Error::Exists(path.display().to_string()).into());
+        }
+    }
+
+    create(&config_path, "inherit = true\n")?;
+    create(&base_path, &scheme_toml())?;
+
+    Ok(())
+}
+
+/// Creates a scheme file for `theme` defining every required role.
+pub(crate) fn scheme(
+    theme: &str,
+    name: &str,
+    config: &Config,
+) -> crate::Result<()> {
+    let theme = ThemeName::parse(theme)?;
+    let name = SchemeName::parse(name)?;
+
+    name.to_ascii()?;
+
+    let theme_dir = config
+        .project
+        .root
+        .join(&config.dirs.themes)
+        .join(theme.as_str());
+
+    if !theme_dir.is_dir() {
+        return Err(Error::MissingTheme(theme.to_string()).into());
+    }
+
+    let theme_config = themes::load_config(&theme, config)?;
+    let path = themes::schemes_dir(theme_config.as_ref(), config)
+        .join(format!("{name}.toml"));
+
+    create(&path, &scheme_toml())?;
+
+    Ok(())
+}
+
+
+/// Every required role pointing at a placeholder swatch, with the optional
+/// ones listed as comments, grouped like `[roles.syntax]`.
+fn scheme_toml() -> String {
+    let mut toml = formatdoc! {r##"
+        [meta]
+        # author = ""
+        # license = ""
+        # blurb = ""
+
+        [palette]
+        # replace with your colors and point the roles below at them
+        {PLACEHOLDER_SWATCH} = "#ff00ff"
+
+        [roles]
+    "##};
+    let mut group = None;
+    let mut lines = Vec::new();
+
+    for role in all_roles() {
+        let (role_group, key) = role
+            .as_str()
+            .split_once('.')
+            .map_or((None, role.as_str()), |(g, k)| (Some(g), k));
+
+        if role_group != group {
+            group = role_group;
+
+            if let Some(group) = group {
+                lines.push(format!("\n[roles.{group}]"));
+            }
+        }
+
+        lines.push(match role.classify() {
+            RoleKind::Base(_) => format!("{key} = \"${PLACEHOLDER_SWATCH}\""),
+            RoleKind::Optional { .. } => format!("# {key}"),
+        });
+    }
+
+    toml.push_str(&lines.join("\n"));
+    toml.push('\n');
+    toml.push_str(indoc! {"
+
+        # [extra]
+        # rainbow = []
+    "});
+
+    toml
+}
+
+fn create(path: &Path, content: &str) -> Result<()> {
+    let writing = |src| Error::Writing {
+        path: path.display().to_string(),
+        src,
+    };
+
+    if path.exists() {
+        return Err(Error::Exists(path.display().to_string()));
+    }
+
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent).map_err(writing)?;
+    }
+
+    fs::write(path, content).map_err(writing)?;
+
+    info!("created `{}`", path.display());
+
+    Ok(())
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::themes::schemes;
+
+    #[test]
+    fn scaffolded_scheme_loads() {
+        let toml = scheme_toml();
+
+        if let Err(e) =
+            schemes::parse("scaffold", Path::new("scaffold.toml"), &toml)
+        {
+            panic!("scaffolded scheme failed to load: {e}\n\n{toml}");
+        }
+    }
+}
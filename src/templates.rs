@@ -1,9 +1,13 @@
+use std::borrow::Cow;
+use std::cell::RefCell;
+use std::collections::BTreeSet;
 use std::fs;
 use std::path::Path;
 use std::result::Result as StdResult;
 
 use anyhow::Context as _;
 use indexmap::IndexMap;
+use log::debug;
 use walkdir::WalkDir;
 
 use crate::extensions::PathExt as _;
@@ -11,9 +15,13 @@ use crate::{Config, Error, Result};
 
 
 pub(crate) mod directives;
+pub(crate) mod library;
+pub(crate) mod lines;
 pub(crate) mod providers;
 
 pub(crate) use self::directives::{Directives, Error as DirectiveError};
+pub(crate) use self::library::Error as LibraryError;
+pub(crate) use self::lines::LineMap;
 pub(crate) use self::providers::{
     Error as ProviderError, Resolved as ResolvedProvider,
 };
@@ -24,6 +32,14 @@ pub(crate) const JINJA_TEMPLATE_SUFFIX: &str = ".jinja";
 pub(crate) const SKIP_RENDERING_PREFIX: char = '_';
 
 
+thread_local! {
+    // outputs render start to finish on one thread, so this only ever holds
+    // what the current one pulled in
+    static LOADED: RefCell<BTreeSet<String>> =
+        const { RefCell::new(BTreeSet::new()) };
+}
+
+
 #[derive(Debug)]
 pub(crate) struct Loader {
     pub env: minijinja::Environment<'static>,
@@ -69,6 +85,9 @@ impl Loader {
         let mut env = minijinja::Environment::new();
 
         env.set_undefined_behavior(minijinja::UndefinedBehavior::SemiStrict);
+        // escaping `.json` and `.yml` outputs as JSON would quote every
+        // color twice; templates quote values themselves
+        env.set_auto_escape_callback(|_| minijinja::AutoEscape::None);
         env.set_trim_blocks(true);
         env.set_lstrip_blocks(true);
 
@@ -76,12 +95,21 @@ impl Loader {
 
         env.add_filter("code", |s: String| -> String { format!("`{s}`") });
 
-        let directives = Self::templates_with_directives(
+        track_loads(&mut env);
+
+        let mut directives = Self::templates_with_directives(
             &mut env,
             &config.dirs.templates,
             &config.strip_directives,
         )?;
 
+        Self::add_library_templates(
+            &mut env,
+            &mut directives,
+            &config.library.templates,
+            &config.strip_directives,
+        )?;
+
         let providers = providers::resolve(&config.providers)?;
 
         Ok(Self {
@@ -153,22 +181,86 @@ impl Loader {
                     })
                     .map_err(Error::template)?;
 
-                let (directives, filtered) = Directives::from_template(
-                    &name,
+                Self::add_template(
+                    env,
+                    &mut directives_map,
+                    name,
                     &raw_src,
                     strip_patterns,
                     path.to_string_lossy().as_str(),
-                )
-                .map_err(Error::Directive)?;
+                )?;
+            }
+        }
 
-                directives_map.insert(name.clone(), directives);
+        Ok(directives_map)
+    }
 
-                env.add_template_owned(name.clone(), filtered)
-                    .with_context(|| format!("compiling template `{name}`"))
-                    .map_err(Error::template)?;
+    /// Layers the selected library templates underneath the project's, so
+    /// project templates with the same name take precedence.
+    fn add_library_templates(
+        env: &mut minijinja::Environment<'static>,
+        directives_map: &mut IndexMap<String, Directives>,
+        patterns: &[String],
+        strip_patterns: &[Vec<String>],
+    ) -> Result<()> {
+        for entry in library::select(patterns)? {
+            for file in entry.files {
+                if directives_map.contains_key(file.path) {
+                    debug!(
+                        "project template `{}` overrides library template `{}`",
+                        file.path, entry.name
+                    );
+
+                    continue;
+                }
+
+                Self::add_template(
+                    env,
+                    directives_map,
+                    file.path.to_owned(),
+                    file.content,
+                    strip_patterns,
+                    &format!("library:{}", file.path),
+                )?;
             }
         }
 
-        Ok(directives_map)
+        Ok(())
     }
+
+    fn add_template(
+        env: &mut minijinja::Environment<'static>,
+        directives_map: &mut IndexMap<String, Directives>,
+        name: String,
+        raw_src: &str,
+        strip_patterns: &[Vec<String>],
+        path: &str,
+    ) -> Result<()> {
+        let (directives, filtered) =
+            Directives::from_template(&name, raw_src, strip_patterns, path)
+                .map_err(Error::Directive)?;
+
+        env.add_template_owned(name.clone(), filtered)
+            .map_err(|e| directives.lines.error(e))?;
+
+        directives_map.insert(name, directives);
+
+        Ok(())
+    }
+}
+
+
+/// Records every template `env` includes, imports or extends, to be picked
+/// up with [`take_loaded`].
+pub(crate) fn track_loads(env: &mut minijinja::Environment<'_>) {
+    env.set_path_join_callback(|name, _| {
+        LOADED.with_borrow_mut(|loaded| loaded.insert(name.to_owned()));
+
+        Cow::Borrowed(name)
+    });
+}
+
+/// The templates loaded on this thread since the last call.
+pub(crate) fn take_loaded() -> BTreeSet<String> {
+    LOADED.take()
 }
@@ -6,6 +6,7 @@ use indexmap::{IndexMap, IndexSet};
 use itertools::Itertools as _;
 
 use self::DirectiveType::{Other, Theymer};
+use super::LineMap;
 use crate::extensions::PathExt as _;
 use crate::output::{ColorStyle, Style, TextStyle};
 
@@ -48,6 +49,9 @@ pub(crate) struct Directives {
     pub style: Arc<Style>,
     pub source: Option<String>,
     pub passthrough: IndexSet<String>,
+
+    /// Maps minijinja's errors back to the lines of the unfiltered file.
+    pub lines: LineMap,
 }
 
 impl Directives {
@@ -61,7 +65,7 @@ impl Directives {
         let mut passthrough = IndexSet::new();
         let mut content_lines = Vec::new();
 
-        for line in content.lines() {
+        for (index, line) in content.lines().enumerate() {
             let classified = Self::classify(line, strip_patterns, path)?;
 
             match classified {
@@ -72,7 +76,7 @@ impl Directives {
                     passthrough.insert(Self::canonicalize(&text));
                 }
                 LineType::Content => {
-                    content_lines.push(line);
+                    content_lines.push((index, line));
                 }
             }
         }
@@ -93,13 +97,15 @@ impl Directives {
 
         passthrough.sort_unstable();
 
-        let filtered = Self::trim_ends(&content_lines);
+        let kept = Self::trim_ends(&content_lines);
+        let filtered = kept.iter().map(|&(_, line)| line).join("\n");
 
         Ok((
             Self {
                 style,
                 source,
                 passthrough,
+                lines: LineMap::new(name, path, content, kept),
             },
             filtered,
         ))
@@ -166,22 +172,26 @@ impl Directives {
         Ok(LineType::Content)
     }
 
-    fn trim_ends(content: &[&str]) -> String {
-        let Some(start) = content.iter().position(|l| !l.trim().is_empty())
+    /// Drops blank lines from both ends of `(index, line)` pairs.
+    fn trim_ends<'a, 'b>(
+        content: &'b [(usize, &'a str)],
+    ) -> &'b [(usize, &'a str)] {
+        let Some(start) =
+            content.iter().position(|(_, l)| !l.trim().is_empty())
         else {
-            return String::new();
+            return &[];
         };
 
         let end = content
             .iter()
-            .rposition(|l| !l.trim().is_empty())
+            .rposition(|(_, l)| !l.trim().is_empty())
             .map_or(content.len(), |i| i + 1);
 
         #[expect(
             clippy::indexing_slicing,
             reason = "start and end are always within bounds"
         )]
-        content[start..end].join("\n")
+        &content[start..end]
     }
 
     fn canonicalize(directive: &str) -> String {
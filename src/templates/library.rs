@@ -0,0 +1,174 @@
+use std::path::{Path, PathBuf};
+use std::result::Result as StdResult;
+use std::{fs, io};
+
+use globset::{Glob, GlobMatcher};
+use itertools::Itertools as _;
+
+
+type Result<T> = StdResult<T, Error>;
+
+#[non_exhaustive]
+#[derive(Debug, thiserror::Error)]
+pub(crate) enum Error {
+    #[error("failed to parse library selector `{pattern}`: {src}")]
+    ParsingGlob {
+        pattern: String,
+        src: globset::Error,
+    },
+
+    #[error("no library template matches `{pattern}`; pick from {available}")]
+    Unknown { pattern: String, available: String },
+
+    #[error("`{0}` already exists; delete it first to eject it again")]
+    Exists(String),
+
+    #[error("failed to write `{path}`: {src}")]
+    Writing { path: String, src: io::Error },
+}
+
+
+#[derive(Debug)]
+pub(crate) struct Entry {
+    pub name: &'static str,
+    pub files: &'static [File],
+}
+
+#[derive(Debug)]
+pub(crate) struct File {
+    pub path: &'static str,
+    pub content: &'static str,
+}
+
+impl Entry {
+    fn matches(&self, matcher: &GlobMatcher) -> bool {
+        matcher.is_match(self.name)
+            || self.files.iter().any(|file| matcher.is_match(file.path))
+    }
+}
+
+// templates this project renders itself are embedded from `templates/`, the
+// rest from `library/`
+macro_rules! library {
+    (
+        $( $name:literal => $dir:ident [ $( $path:literal ),+ $( , )? ] ),*
+        $( , )?
+    ) => {
+        pub(crate) const LIBRARY: &[Entry] = &[
+            $(
+                Entry {
+                    name: $name,
+                    files: &[
+                        $(
+                            File {
+                                path: $path,
+                                content: include_str!(concat!(
+                                    env!("CARGO_MANIFEST_DIR"),
+                                    "/",
+                                    stringify!($dir),
+                                    "/",
+                                    $path
+                                )),
+                            },
+                        )+
+                    ],
+                },
+            )*
+        ];
+    };
+}
+
+library! {
+    "alacritty" => library ["ports/alacritty/SCHEME.toml.jinja"],
+    "bat" => library ["ports/bat/SCHEME.tmTheme.jinja"],
+    "circles" => templates ["assets/circles/SWATCH.svg.jinja"],
+    "delta" => library ["ports/delta/SCHEME.gitconfig.jinja"],
+    "fish" => templates ["ports/fish/SCHEME.theme.jinja"],
+    "foot" => library ["ports/foot/SCHEME.ini.jinja"],
+    "ghostty" => templates ["ports/ghostty/SCHEME.jinja"],
+    "helix" => templates ["ports/helix/SCHEME.toml.jinja"],
+    "iterm2" => templates ["ports/iterm2/SCHEME.itermcolors.jinja"],
+    "kitty" => templates ["ports/kitty/SCHEME.conf.jinja"],
+    "lazygit" => library ["ports/lazygit/SCHEME.yml.jinja"],
+    "neovim" => library ["ports/neovim/colors/SCHEME.lua.jinja"],
+    "starship" => templates ["ports/starship/SCHEME.toml.jinja"],
+    "tmux" => library ["ports/tmux/SCHEME.tmux.jinja"],
+    "vscode" => library ["ports/vscode/themes/SCHEME-color-theme.json.jinja"],
+    "wezterm" => library ["ports/wezterm/SCHEME.toml.jinja"],
+    "xresources" => library ["ports/xresources/SCHEME.Xresources.jinja"],
+}
+
+
+/// Library entries matching any of `patterns` by name or template path.
+/// Every pattern has to match at least one entry.
+pub(crate) fn select(patterns: &[String]) -> Result<Vec<&'static Entry>> {
+    let mut selected: Vec<&Entry> = Vec::new();
+
+    for pattern in patterns {
+        let matcher = Glob::new(pattern)
+            .map_err(|src| Error::ParsingGlob {
+                pattern: pattern.clone(),
+                src,
+            })?
+            .compile_matcher();
+        let mut found = false;
+
+        for entry in LIBRARY.iter().filter(|entry| entry.matches(&matcher)) {
+            found = true;
+
+            if !selected.iter().any(|e| e.name == entry.name) {
+                selected.push(entry);
+            }
+        }
+
+        if !found {
+            return Err(unknown(pattern));
+        }
+    }
+
+    Ok(selected)
+}
+
+pub(crate) fn find(name: &str) -> Result<&'static Entry> {
+    LIBRARY
+        .iter()
+        .find(|entry| entry.name == name)
+        .ok_or_else(|| unknown(name))
+}
+
+/// Copies every file of the library template `name` into `dir`, keeping
+/// their paths, and returns where they were written.
+pub(crate) fn eject(name: &str, dir: &Path) -> Result<Vec<PathBuf>> {
+    let entry = find(name)?;
+    let paths: Vec<PathBuf> =
+        entry.files.iter().map(|file| dir.join(file.path)).collect();
+
+    if let Some(existing) = paths.iter().find(|path| path.exists()) {
+        return Err(Error::Exists(existing.display().to_string()));
+    }
+
+    for (file, path) in entry.files.iter().zip(&paths) {
+        let writing = |src| Error::Writing {
+            path: path.display().to_string(),
+            src,
+        };
+
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent).map_err(writing)?;
+        }
+
+        fs::write(path, file.content).map_err(writing)?;
+    }
+
+    Ok(paths)
+}
+
+fn unknown(pattern: &str) -> Error {
+    Error::Unknown {
+        pattern: pattern.to_owned(),
+        available: LIBRARY
+            .iter()
+            .map(|entry| format!("`{}`", entry.name))
+            .join(", "),
+    }
+}
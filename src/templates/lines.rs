@@ -0,0 +1,165 @@
+use std::ops::Range;
+
+use minijinja::ErrorKind;
+
+use crate::diagnostics::{Diagnostic, Location};
+
+
+#[derive(Debug)]
+pub(crate) struct LineMap {
+    name: String,
+    path: String,
+    content: String,
+    lines: Vec<Line>,
+}
+
+/// Where a line kept for minijinja starts, in both sources.
+#[derive(Debug, Clone, Copy)]
+struct Line {
+    filtered: usize,
+    original: usize,
+}
+
+impl LineMap {
+    /// Maps the lines of `content` kept for minijinja, given by their index
+    /// and text, in the order they were joined.
+    pub(crate) fn new(
+        name: &str,
+        path: &str,
+        content: &str,
+        kept: &[(usize, &str)],
+    ) -> Self {
+        let starts: Vec<usize> = content
+            .split_inclusive('\n')
+            .scan(0, |offset, line| {
+                let start = *offset;
+                *offset += line.len();
+
+                Some(start)
+            })
+            .collect();
+        let mut lines = Vec::new();
+        let mut filtered = 0;
+
+        for &(index, text) in kept {
+            lines.push(Line {
+                filtered,
+                original: starts.get(index).copied().unwrap_or_default(),
+            });
+
+            filtered += text.len() + 1;
+        }
+
+        Self {
+            name: name.to_owned(),
+            path: path.to_owned(),
+            content: content.to_owned(),
+            lines,
+        }
+    }
+
+    /// Rewrites `err` to point at the original file, naming the undefined
+    /// expression if that's what went wrong.
+    pub(crate) fn error(&self, err: minijinja::Error) -> crate::Error {
+        let Some(span) = self.span(&err) else {
+            return crate::Error::template(err);
+        };
+
+        let expression = self
+            .content
+            .get(span.clone())
+            .map(str::trim)
+            .filter(|expression| {
+                err.kind() == ErrorKind::UndefinedError
+                    && !expression.is_empty()
+            })
+            .map(|expression| format!(" `{expression}`"))
+            .unwrap_or_default();
+        let detail = err
+            .detail()
+            .map(|detail| format!(": {detail}"))
+            .unwrap_or_default();
+        let message = format!("{}{expression}{detail}", err.kind());
+
+        Diagnostic {
+            location: Some(Location::new(
+                self.path.clone(),
+                &self.content,
+                span,
+            )),
+            help: None,
+        }
+        .attach(crate::Error::template(anyhow::Error::msg(message)))
+    }
+
+    /// The byte range of `err` in the original file, if it happened in this
+    /// template.
+    fn span(&self, err: &minijinja::Error) -> Option<Range<usize>> {
+        if err.name() != Some(self.name.as_str()) {
+            return None;
+        }
+
+        let Some(range) = err.range() else {
+            let line = self.lines.get(err.line()?.checked_sub(1)?)?;
+
+            return Some(line.original..line.original);
+        };
+
+        let index = self
+            .lines
+            .partition_point(|line| line.filtered <= range.start)
+            .checked_sub(1)?;
+        let line = self.lines.get(index)?;
+        let start = range.start - line.filtered + line.original;
+
+        Some(start..start + range.len())
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use indoc::indoc;
+    use minijinja::{Environment, UndefinedBehavior, context};
+
+    use crate::templates::Directives;
+
+    #[test]
+    fn points_at_original_line() {
+        let content = indoc! {"
+            #theymer: source = https://example.com/theme.toml
+
+            bg = {{ bg.hex }}
+            fg = {{ fg.hx }}
+        "};
+        let (directives, filtered) =
+            Directives::from_template("theme", content, &[], "theme.jinja")
+                .unwrap_or_else(|e| panic!("failed to parse directives: {e}"));
+
+        let mut env = Environment::new();
+        env.set_undefined_behavior(UndefinedBehavior::SemiStrict);
+        env.add_template_owned("theme", filtered)
+            .unwrap_or_else(|e| panic!("failed to add template: {e}"));
+
+        let rendered = env.get_template("theme").and_then(|template| {
+            template.render(context! {
+                bg => context! { hex => "#181716" },
+                fg => context! { hex => "#f5eeec" },
+            })
+        });
+        let error = match rendered {
+            Ok(output) => panic!("render should fail, got:\n{output}"),
+            Err(e) => directives.lines.error(e).to_string(),
+        };
+
+        assert!(
+            error.contains("--> theme.jinja:4:"),
+            "wrong location in:\n{error}"
+        );
+        assert!(
+            error.contains("4 | fg = {{ fg.hx }}"),
+            "missing snippet in:\n{error}"
+        );
+        assert!(error.contains("`fg.hx`"), "missing variable in:\n{error}");
+    }
+}
@@ -1,28 +1,37 @@
-use std::path::Path;
+use std::path::{Path, PathBuf};
 use std::result::Result as StdResult;
 use std::{fs, io};
 use walkdir::WalkDir;
 
-use indexmap::IndexMap;
+use indexmap::{IndexMap, IndexSet};
+use itertools::Itertools as _;
 use serde::{Deserialize, Serialize};
 
 use crate::ProjectType;
+use crate::diagnostics::Source;
 use crate::extensions::Merge as _;
 use crate::output::{Ascii, Unicode};
+use crate::render::Failures;
 
 
 pub(crate) mod schemes;
 
 mod config;
+mod explain;
 mod names;
 mod roles;
 mod swatches;
 
 pub(crate) use self::config::Config;
+pub(crate) use self::explain::{
+    GraphFormat, Origin as RoleOrigin, chain as explain_role,
+    graph as role_graph,
+};
 pub(crate) use self::names::{Error as NameError, Validated as ValidatedName};
 pub(crate) use self::roles::{
     Error as RoleError, Kind as RoleKind, Name as RoleName,
     Resolved as ResolvedRole, ResolvedRoles, Roles, Value as RoleValue,
+    base as base_roles, iter as all_roles,
 };
 pub(crate) use self::schemes::{
     Error as SchemeError, Extra, Meta, Name as SchemeName, Raw as RawScheme,
@@ -33,7 +42,7 @@ pub(crate) use self::swatches::{
 };
 
 
-const BASE_FILENAME: &str = "theme.toml";
+pub(crate) const BASE_FILENAME: &str = "theme.toml";
 
 
 pub(crate) type Name = ValidatedName<"theme", Unicode>;
@@ -49,13 +58,25 @@ pub(crate) enum Error {
     )]
     MissingThemeBaseAndSchemesDir { theme: String, schemes_dir: String },
 
+    #[error("no theme named `{0}`")]
+    UnknownTheme(String),
+
+    #[error("multiple themes found; pick one of {0}")]
+    AmbiguousTheme(String),
+
+    #[error("theme `{theme}` has no scheme named `{scheme}`")]
+    UnknownScheme { theme: String, scheme: String },
+
+    #[error("theme `{theme}` has multiple schemes; pick one of {schemes}")]
+    AmbiguousScheme { theme: String, schemes: String },
+
     #[error("failed to read directory `{0}` (invalid utf-8?)")]
     ReadingDir(String),
 
     #[error("failed to read theme file `{path}`: {src}")]
     Reading { path: String, src: io::Error },
 
-    #[error("failed to parse theme file `{path}`: {src}")]
+    #[error("failed to parse theme file `{path}`: {}", src.message())]
     Parsing {
         path: String,
         src: Box<toml::de::Error>,
@@ -98,33 +119,78 @@ enum Type {
 }
 
 
+/// Loads every theme in the project. Themes and schemes that fail to load
+/// are recorded in `failures` and left out, so the rest can still render.
 pub(crate) fn load_all(
     config: &crate::Config,
+    failures: &mut Failures,
 ) -> crate::Result<IndexMap<Name, Theme>> {
-    discover_themes(config)?
-        .into_iter()
-        .map(|name| {
-            let theme = load(name, config)?;
+    let mut themes = IndexMap::new();
+
+    for name in discover_themes(config)? {
+        let dir = theme_dir(&name, config);
 
-            Ok((theme.name.clone(), theme))
-        })
-        .collect()
+        match load(name, config, failures) {
+            Ok(theme) => {
+                themes.insert(theme.name.clone(), theme);
+            }
+            Err(e) => failures.push_load(&dir, &e),
+        }
+    }
+
+    Ok(themes)
+}
+
+
+/// Looks up a theme and one of its schemes by name. Either can be omitted
+/// when there's only one to choose from.
+pub(crate) fn find<'a>(
+    themes: &'a IndexMap<Name, Theme>,
+    theme: Option<&str>,
+    scheme: Option<&str>,
+) -> crate::Result<(&'a Theme, &'a Scheme)> {
+    let theme = match theme {
+        Some(name) => themes
+            .values()
+            .find(|theme| theme.name.as_str() == name)
+            .ok_or_else(|| Error::UnknownTheme(name.to_owned()))?,
+        None => themes.values().exactly_one().map_err(|themes| {
+            Error::AmbiguousTheme(
+                themes.map(|t| format!("`{}`", t.name)).join(", "),
+            )
+        })?,
+    };
+
+    let scheme = match scheme {
+        Some(name) => theme
+            .schemes
+            .values()
+            .find(|scheme| scheme.name.as_str() == name)
+            .ok_or_else(|| Error::UnknownScheme {
+                theme: theme.name.to_string(),
+                scheme: name.to_owned(),
+            })?,
+        None => theme.schemes.values().exactly_one().map_err(|schemes| {
+            Error::AmbiguousScheme {
+                theme: theme.name.to_string(),
+                schemes: schemes.map(|s| format!("`{}`", s.name)).join(", "),
+            }
+        })?,
+    };
+
+    Ok((theme, scheme))
 }
 
 
 // TODO: rewrite this to be cleaner
-pub(crate) fn load(name: Name, config: &crate::Config) -> crate::Result<Theme> {
-    let themes_dir = config
-        .project
-        .root
-        .join(&config.dirs.themes)
-        .join(name.as_str());
+pub(crate) fn load(
+    name: Name,
+    config: &crate::Config,
+    failures: &mut Failures,
+) -> crate::Result<Theme> {
+    let themes_dir = theme_dir(&name, config);
     let theme_config = config::load(&themes_dir, &name, config)?;
-
-    let schemes_dir = theme_config.as_ref().map_or_else(
-        || config.dirs.schemes.clone(),
-        |tc| tc.dirs.schemes.clone(),
-    );
+    let schemes_dir = schemes_dir(theme_config.as_ref(), config);
 
     let base_path = themes_dir.join(BASE_FILENAME);
 
@@ -175,7 +241,7 @@ pub(crate) fn load(name: Name, config: &crate::Config) -> crate::Result<Theme> {
             if schemes_dir.exists() && schemes_dir.is_dir() {
                 let base_scheme = base.as_ref().map(|b| &b.raw_scheme);
 
-                load_schemes(&schemes_dir, base_scheme)?
+                load_schemes(&schemes_dir, base_scheme, failures)?
             } else if let Some(base) = base {
                 let mut schemes = IndexMap::new();
                 let scheme = base.raw_scheme.into_scheme(name.as_str())?;
@@ -202,6 +268,35 @@ pub(crate) fn load(name: Name, config: &crate::Config) -> crate::Result<Theme> {
 }
 
 
+/// Where a theme's schemes live: its own schemes directory if it has a
+/// config, the project's otherwise.
+pub(crate) fn schemes_dir(
+    theme_config: Option<&Config>,
+    config: &crate::Config,
+) -> PathBuf {
+    theme_config.map_or_else(
+        || config.dirs.schemes.clone(),
+        |tc| tc.dirs.schemes.clone(),
+    )
+}
+
+/// Loads just the config of the theme `name`, without its schemes.
+pub(crate) fn load_config(
+    name: &Name,
+    config: &crate::Config,
+) -> crate::Result<Option<Config>> {
+    config::load(&theme_dir(name, config), name, config)
+}
+
+fn theme_dir(name: &Name, config: &crate::Config) -> PathBuf {
+    config
+        .project
+        .root
+        .join(&config.dirs.themes)
+        .join(name.as_str())
+}
+
+
 fn discover_themes(config: &crate::Config) -> crate::Result<Vec<Name>> {
     match config.project.r#type {
         ProjectType::Monotheme => {
@@ -243,6 +338,7 @@ fn discover_themes(config: &crate::Config) -> crate::Result<Vec<Name>> {
 fn load_schemes(
     dir: &Path,
     base: Option<&RawScheme>,
+    failures: &mut Failures,
 ) -> crate::Result<IndexMap<SchemeName, Scheme>> {
     let mut schemes = IndexMap::new();
 
@@ -261,18 +357,41 @@ fn load_schemes(
             .file_stem()
             .and_then(|s| s.to_str())
             .ok_or_else(|| Error::ReadingDir(path.display().to_string()))?;
-        let mut raw = schemes::load_raw(path)?;
 
-        if let Some(base) = base {
-            raw = raw.merge(base.clone());
+        match load_scheme(path, name, base) {
+            Ok(scheme) => {
+                schemes.insert(scheme.name.clone(), scheme);
+            }
+            Err(e) => failures.push_load(path, &e),
         }
+    }
 
-        let scheme = raw.into_scheme(name)?;
+    Ok(schemes)
+}
 
-        schemes.insert(scheme.name.clone(), scheme);
+fn load_scheme(
+    path: &Path,
+    name: &str,
+    base: Option<&RawScheme>,
+) -> crate::Result<Scheme> {
+    let mut raw = schemes::load_raw(path)?;
+    let mut inherited = IndexSet::new();
+
+    if let Some(base) = base {
+        inherited.extend(
+            base.roles
+                .set_roles()
+                .filter(|role| !raw.roles.contains_role(role)),
+        );
+
+        raw = raw.merge(base.clone());
     }
 
-    Ok(schemes)
+    let mut scheme = raw.into_scheme(name)?;
+
+    scheme.inherited_roles = inherited;
+
+    Ok(scheme)
 }
 
 
@@ -282,11 +401,18 @@ fn load_base(path: &Path) -> crate::Result<Base> {
         src,
     })?;
 
-    let root: toml::Table =
-        toml::from_str(&content).map_err(|src| Error::Parsing {
-            path: path.display().to_string(),
-            src: Box::new(src),
-        })?;
+    let root: toml::Table = toml::from_str(&content).map_err(|src| {
+        let span = src.span();
+
+        Source::new(path, &content).error(
+            Error::Parsing {
+                path: path.display().to_string(),
+                src: Box::new(src),
+            },
+            span,
+            None,
+        )
+    })?;
 
     let name_ascii = root
         .get("name_ascii")
@@ -60,7 +60,7 @@ pub(crate) fn load(
         src,
     })?;
 
-    let raw: Raw = config::parse(content.as_str())?;
+    let raw: Raw = config::parse(&path, content.as_str())?;
 
     Ok(Some(Config {
         inherit: raw.inherit,
@@ -0,0 +1,202 @@
+use std::fmt::{Display, Formatter, Result as FmtResult};
+
+use indexmap::{IndexMap, IndexSet};
+use itertools::Itertools as _;
+
+use super::{RoleError, RoleKind, RoleName, RoleValue, Scheme, roles};
+use crate::Result;
+
+/// Where a step in a role's resolution chain got its value from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Origin {
+    /// Set in the scheme itself.
+    Explicit,
+    /// Set in the theme's `theme.toml` and merged into the scheme.
+    Inherited,
+    /// Unset optional role falling back to its base role.
+    Fallback,
+}
+
+impl Display for Origin {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        f.pad(match self {
+            Self::Explicit => "explicit",
+            Self::Inherited => "inherited",
+            Self::Fallback => "fallback",
+        })
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub(crate) enum Target<'a> {
+    Role(RoleName),
+    Swatch(&'a str),
+}
+
+impl Display for Target<'_> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        match self {
+            Self::Role(name) => write!(f, "{name}"),
+            Self::Swatch(name) => write!(f, "${name}"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct Step<'a> {
+    pub role: RoleName,
+    pub origin: Origin,
+    pub target: Target<'a>,
+}
+
+#[derive(Debug, Clone, Copy, clap::ValueEnum)]
+pub(crate) enum GraphFormat {
+    Dot,
+    Mermaid,
+}
+
+/// The direct dependency of `role` in `scheme`, i.e. a single resolution
+/// step.
+fn step(scheme: &Scheme, role: RoleName) -> Result<Step<'_>> {
+    let origin = if scheme.inherited_roles.contains(&role) {
+        Origin::Inherited
+    } else {
+        Origin::Explicit
+    };
+
+    let (origin, target) = match scheme.roles.get(&role) {
+        Some(RoleValue::Swatch(name)) => {
+            (origin, Target::Swatch(name.as_str()))
+        }
+        Some(RoleValue::Role(name)) => (origin, Target::Role(*name)),
+        None => match role.classify() {
+            RoleKind::Optional { base } => {
+                (Origin::Fallback, Target::Role(base))
+            }
+            RoleKind::Base(_) => {
+                return Err(RoleError::MissingRequired(role.to_string()).into());
+            }
+        },
+    };
+
+    Ok(Step {
+        role,
+        origin,
+        target,
+    })
+}
+
+/// Follows `role` through every role-to-role reference and fallback until it
+/// reaches a swatch.
+pub(crate) fn chain(scheme: &Scheme, role: RoleName) -> Result<Vec<Step<'_>>> {
+    let mut steps = Vec::new();
+    let mut visited = IndexSet::new();
+    let mut current = role;
+
+    loop {
+        if !visited.insert(current) {
+            let mut cycle: Vec<String> =
+                visited.iter().map(ToString::to_string).collect();
+            cycle.push(current.to_string());
+
+            return Err(RoleError::CircularReference(cycle).into());
+        }
+
+        let step = step(scheme, current)?;
+
+        steps.push(step);
+
+        match step.target {
+            Target::Role(next) => current = next,
+            Target::Swatch(_) => return Ok(steps),
+        }
+    }
+}
+
+/// Every role of `scheme` with its direct dependency, as a DOT or Mermaid
+/// flowchart.
+pub(crate) fn graph(scheme: &Scheme, format: GraphFormat) -> Result<String> {
+    let steps = roles::iter()
+        .map(|role| step(scheme, role))
+        .collect::<Result<Vec<_>>>()?;
+
+    Ok(match format {
+        GraphFormat::Dot => dot(scheme, &steps),
+        GraphFormat::Mermaid => mermaid(&steps),
+    })
+}
+
+fn dot(scheme: &Scheme, steps: &[Step<'_>]) -> String {
+    let mut lines = vec![
+        format!("digraph \"{}\" {{", scheme.name),
+        "  rankdir=LR;".to_owned(),
+        "  node [shape=box];".to_owned(),
+    ];
+
+    for swatch in &scheme.palette {
+        lines.push(format!(
+            "  \"${}\" [shape=ellipse, style=filled, fillcolor=\"{}\"];",
+            swatch.name,
+            swatch.hex()
+        ));
+    }
+
+    for step in steps {
+        let attrs = match step.origin {
+            Origin::Explicit => "",
+            Origin::Inherited => " [style=bold, label=\"inherited\"]",
+            Origin::Fallback => " [style=dashed, label=\"fallback\"]",
+        };
+
+        lines.push(format!(
+            "  \"{}\" -> \"{}\"{attrs};",
+            step.role, step.target
+        ));
+    }
+
+    lines.push("}".to_owned());
+
+    lines.join("\n")
+}
+
+fn mermaid(steps: &[Step<'_>]) -> String {
+    // mermaid ids can't contain `.` or `$`, so every node gets a numbered id
+    let mut ids: IndexMap<String, String> = IndexMap::new();
+    let mut node = |label: String, swatch: bool| -> String {
+        let next = ids.len();
+
+        ids.entry(label.clone())
+            .or_insert_with(|| {
+                if swatch {
+                    format!("n{next}([\"{label}\"])")
+                } else {
+                    format!("n{next}[\"{label}\"]")
+                }
+            })
+            .clone()
+    };
+
+    let edges = steps
+        .iter()
+        .map(|step| {
+            let from = node(step.role.to_string(), false);
+            let to = node(
+                step.target.to_string(),
+                matches!(step.target, Target::Swatch(_)),
+            );
+            let arrow = match step.origin {
+                Origin::Explicit => "-->",
+                Origin::Inherited => "==>|inherited|",
+                Origin::Fallback => "-.->|fallback|",
+            };
+
+            format!("  {from} {arrow} {to}")
+        })
+        .collect_vec();
+
+    let mut lines = vec!["flowchart LR".to_owned()];
+
+    lines.extend(edges);
+
+    lines.join("\n")
+}
@@ -440,7 +440,7 @@ impl Name {
     }
 
     #[must_use]
-    pub(crate) const fn as_str(&self) -> &str {
+    pub(crate) const fn as_str(&self) -> &'static str {
         self.0
     }
 }
@@ -11,6 +11,7 @@ use super::{
     RoleValue, Roles, Swatch, SwatchError, ValidatedName, roles,
 };
 use crate::Result;
+use crate::diagnostics::{Diagnostic, Location, Source, did_you_mean};
 use crate::extensions::PathExt as _;
 use crate::output::{Ascii, Unicode};
 
@@ -35,7 +36,7 @@ pub(crate) enum Error {
     #[error("invalid structure in `{path}`: {reason}")]
     InvalidStructure { path: String, reason: String },
 
-    #[error("invalid toml syntax in `{path}`: {src}")]
+    #[error("invalid toml syntax in `{path}`: {}", src.message())]
     ParsingRaw {
         path: String,
         src: Box<toml::de::Error>,
@@ -63,6 +64,10 @@ pub(crate) struct Scheme {
     #[serde(skip)]
     pub roles: Roles,
 
+    /// Roles merged in from the theme's base `theme.toml`.
+    #[serde(skip)]
+    pub inherited_roles: IndexSet<RoleName>,
+
     #[serde(flatten)]
     pub resolved_roles: ResolvedRoles,
 
@@ -77,6 +82,10 @@ pub(crate) struct Raw {
     pub palette: Palette,
     pub roles: Roles,
     pub extra: Option<Extra>,
+
+    /// Where each role was assigned, to point at it when it can't resolve.
+    #[serde(skip)]
+    pub role_locations: IndexMap<RoleName, Location>,
 }
 
 impl Raw {
@@ -97,6 +106,7 @@ impl Raw {
             meta: self.meta.clone(),
             palette: self.palette,
             roles: self.roles,
+            inherited_roles: IndexSet::new(),
             resolved_roles,
             extra: self.extra,
             resolved_extra,
@@ -154,9 +164,13 @@ impl Raw {
                 let swatch = self
                     .palette
                     .get(display_name.as_str())
-                    .ok_or_else(|| Error::UndefinedSwatch {
-                        role: role.to_string(),
-                        swatch: display_name.to_string(),
+                    .ok_or_else(|| {
+                        undefined_swatch(
+                            role.as_str(),
+                            display_name.as_str(),
+                            &self.palette,
+                            self.role_locations.get(&role),
+                        )
                     })?;
 
                 Ok(Self::resolved_role_from(swatch))
@@ -219,10 +233,7 @@ impl Raw {
         match value {
             RoleValue::Swatch(name) => {
                 let swatch = palette.get(name.as_str()).ok_or_else(|| {
-                    Error::UndefinedSwatch {
-                        role: role.to_owned(),
-                        swatch: name.to_string(),
-                    }
+                    undefined_swatch(role, name.as_str(), palette, None)
                 })?;
 
                 Ok(Self::resolved_role_from(swatch))
@@ -248,50 +259,90 @@ impl Raw {
         Ok((name, name_ascii))
     }
 
-    fn parse_roles(roles_val: &toml::Value, path: &String) -> Result<Roles> {
+    fn parse_roles(
+        roles_val: &toml::Value,
+        palette: &Palette,
+        source: &Source<'_>,
+        path: &str,
+    ) -> Result<(Roles, IndexMap<RoleName, Location>)> {
         let mut roles = Roles::new();
+        let mut locations = IndexMap::new();
 
-        let table =
-            roles_val
-                .as_table()
-                .ok_or_else(|| Error::InvalidStructure {
+        let table = roles_val.as_table().ok_or_else(|| {
+            source.error(
+                Error::InvalidStructure {
                     path: path.to_owned(),
                     reason: "`roles` must be a table".to_owned(),
-                })?;
+                },
+                source.value(&["roles"]),
+                None,
+            )
+        })?;
+
+        let mut insert = |keys: &[&str], val: &toml::Value| -> Result<()> {
+            let (name, value) =
+                Self::parse_role(keys, val, palette, source, path)?;
+
+            if let Some(span) = source.value(keys) {
+                locations.insert(name, source.locate(span));
+            }
+
+            roles.insert(name, value);
+
+            Ok(())
+        };
 
         for (key, val) in table {
             if let Some(nested_table) = val.as_table() {
                 for (nested_key, nested_val) in nested_table {
-                    let full_key = format!("{key}.{nested_key}");
-                    Self::parse_role(&full_key, nested_val, path, &mut roles)?;
+                    insert(
+                        &["roles", key.as_str(), nested_key.as_str()],
+                        nested_val,
+                    )?;
                 }
             } else {
-                Self::parse_role(key, val, path, &mut roles)?;
+                insert(&["roles", key.as_str()], val)?;
             }
         }
 
-        Ok(roles)
+        Ok((roles, locations))
     }
 
+    /// Parses the role at `keys`, e.g. `["roles", "ansi", "red"]`.
     fn parse_role(
-        role_key: &str,
+        keys: &[&str],
         val: &toml::Value,
+        palette: &Palette,
+        source: &Source<'_>,
         path: &str,
-        parsed: &mut Roles,
-    ) -> Result<()> {
-        let role_name =
-            role_key.parse().map_err(|_src| Error::InvalidStructure {
-                path: path.to_owned(),
-                reason: format!("invalid role name: `{role_key}`"),
-            })?;
-        let val_str = val.as_str().ok_or_else(|| Error::InvalidStructure {
-            path: path.to_owned(),
-            reason: format!("role `{role_key}` must be a string"),
+    ) -> Result<(RoleName, RoleValue)> {
+        let role_key = keys.get(1..).unwrap_or_default().join(".");
+        let role_name = role_key.parse().map_err(|_src| {
+            source.error(
+                Error::InvalidStructure {
+                    path: path.to_owned(),
+                    reason: format!("invalid role name: `{role_key}`"),
+                },
+                source.key(keys),
+                did_you_mean(&role_key, roles::iter().map(|r| r.as_str()))
+                    .map(|role| format!("did you mean `{role}`?")),
+            )
+        })?;
+        let val_str = val.as_str().ok_or_else(|| {
+            source.error(
+                Error::InvalidStructure {
+                    path: path.to_owned(),
+                    reason: format!("role `{role_key}` must be a string"),
+                },
+                source.value(keys),
+                None,
+            )
+        })?;
+        let value = RoleValue::parse(val_str).map_err(|e| {
+            source.error(e, source.value(keys), suggest_value(val_str, palette))
         })?;
 
-        parsed.insert(role_name, RoleValue::parse(val_str)?);
-
-        Ok(())
+        Ok((role_name, value))
     }
 
     fn parse_extra(val: &toml::Value, path: &str) -> Result<Extra> {
@@ -331,19 +382,42 @@ impl Raw {
         Ok(Extra { rainbow })
     }
 
-    fn parse_palette(val: &toml::Value, path: &str) -> Result<Palette> {
-        let table = val.as_table().ok_or_else(|| Error::Deserializing {
-            section: "palette".to_owned(),
-            path: path.to_owned(),
-            src: Box::new(<toml::de::Error as serde::de::Error>::custom(
-                "palette must be a table",
-            )),
+    fn parse_palette(
+        val: &toml::Value,
+        source: &Source<'_>,
+        path: &str,
+    ) -> Result<Palette> {
+        let table = val.as_table().ok_or_else(|| {
+            source.error(
+                Error::Deserializing {
+                    section: "palette".to_owned(),
+                    path: path.to_owned(),
+                    src: Box::new(
+                        <toml::de::Error as serde::de::Error>::custom(
+                            "palette must be a table",
+                        ),
+                    ),
+                },
+                source.value(&["palette"]),
+                None,
+            )
         })?;
 
         let mut palette = Palette::new();
 
         for (display_key, v) in table {
-            let swatch = Swatch::parse(display_key, v)?;
+            let swatch = Swatch::parse(display_key, v).map_err(|e| {
+                let keys = ["palette", display_key.as_str()];
+                // bad names are pointed at by their key, bad colors by their
+                // value
+                let span = if matches!(e, crate::Error::Name(_)) {
+                    source.key(&keys)
+                } else {
+                    source.value(&keys)
+                };
+
+                source.error(e, span, None)
+            })?;
             palette.insert(swatch);
         }
 
@@ -457,20 +531,39 @@ pub(crate) fn load(name: &str, path: &Path) -> Result<Scheme> {
     raw.into_scheme(name)
 }
 
+/// Like [`load`], for a scheme that isn't on disk yet. `path` is only used in
+/// errors.
+pub(crate) fn parse(name: &str, path: &Path, content: &str) -> Result<Scheme> {
+    let raw = parse_raw(path, content)?;
+
+    raw.into_scheme(name)
+}
+
 pub(crate) fn load_raw(path: &Path) -> Result<Raw> {
+    let content = fs::read_to_string(path).map_err(|src| Error::Reading {
+        path: path.display().to_string(),
+        src,
+    })?;
+
+    parse_raw(path, &content)
+}
+
+fn parse_raw(path: &Path, content: &str) -> Result<Raw> {
     let path_str = path.display().to_string();
+    let source = Source::new(path, content);
 
-    let content =
-        fs::read_to_string(&path_str).map_err(|src| Error::Reading {
-            path: path_str.clone(),
-            src,
-        })?;
+    let root: toml::Table = toml::from_str(content).map_err(|src| {
+        let span = src.span();
 
-    let root: toml::Table =
-        toml::from_str(&content).map_err(|src| Error::ParsingRaw {
-            path: path_str.clone(),
-            src: Box::new(src),
-        })?;
+        source.error(
+            Error::ParsingRaw {
+                path: path_str.clone(),
+                src: Box::new(src),
+            },
+            span,
+            None,
+        )
+    })?;
 
     let name_ascii: Option<AsciiName> = root
         .get("scheme_ascii")
@@ -490,27 +583,34 @@ pub(crate) fn load_raw(path: &Path) -> Result<Raw> {
                 )),
             })
         })
-        .transpose()?;
+        .transpose()
+        .map_err(|e| source.error(e, source.value(&["scheme_ascii"]), None))?;
 
     let meta: Meta = root
         .get("meta")
         .map(|v| {
-            v.clone().try_into().map_err(|src| Error::Deserializing {
-                section: "meta".to_owned(),
-                path: path_str.clone(),
-                src: Box::new(src),
+            v.clone().try_into().map_err(|src| {
+                source.error(
+                    Error::Deserializing {
+                        section: "meta".to_owned(),
+                        path: path_str.clone(),
+                        src: Box::new(src),
+                    },
+                    source.value(&["meta"]),
+                    None,
+                )
             })
         })
         .transpose()?
         .unwrap_or_default();
 
     // TODO: refactor into `validate_meta`
-    validate_meta_field("author", meta.author.as_ref())?;
-    validate_meta_field("author_ascii", meta.author_ascii.as_ref())?;
-    validate_meta_field("license", meta.license.as_ref())?;
-    validate_meta_field("license_ascii", meta.license_ascii.as_ref())?;
-    validate_meta_field("blurb", meta.blurb.as_ref())?;
-    validate_meta_field("blurb_ascii", meta.blurb_ascii.as_ref())?;
+    validate_meta_field(&source, "author", meta.author.as_ref())?;
+    validate_meta_field(&source, "author_ascii", meta.author_ascii.as_ref())?;
+    validate_meta_field(&source, "license", meta.license.as_ref())?;
+    validate_meta_field(&source, "license_ascii", meta.license_ascii.as_ref())?;
+    validate_meta_field(&source, "blurb", meta.blurb.as_ref())?;
+    validate_meta_field(&source, "blurb_ascii", meta.blurb_ascii.as_ref())?;
 
     let palette_val =
         root.get("palette").ok_or_else(|| Error::Deserializing {
@@ -521,7 +621,7 @@ pub(crate) fn load_raw(path: &Path) -> Result<Raw> {
             ),
         })?;
 
-    let palette = Raw::parse_palette(palette_val, &path_str)?;
+    let palette = Raw::parse_palette(palette_val, &source, &path_str)?;
 
     let roles_val = root.get("roles").ok_or_else(|| Error::Deserializing {
         section: "roles".to_owned(),
@@ -531,7 +631,8 @@ pub(crate) fn load_raw(path: &Path) -> Result<Raw> {
         )),
     })?;
 
-    let roles = Raw::parse_roles(roles_val, &path_str)?;
+    let (roles, role_locations) =
+        Raw::parse_roles(roles_val, &palette, &source, &path_str)?;
 
     let extra = match root.get("extra") {
         Some(val) => Some(Raw::parse_extra(val, &path_str)?),
@@ -544,34 +645,82 @@ pub(crate) fn load_raw(path: &Path) -> Result<Raw> {
         palette,
         roles,
         extra,
+        role_locations,
     };
 
     Ok(raw)
 }
 
-fn validate_meta_field(name: &str, value: Option<&String>) -> Result<()> {
+fn validate_meta_field(
+    source: &Source<'_>,
+    name: &str,
+    value: Option<&String>,
+) -> Result<()> {
     if let Some(text) = value
         && text.len() > MAX_META_FIELD_LENGTH
     {
-        return Err(Error::InvalidMeta {
-            field: name.to_owned(),
-            reason: format!(
-                "too long ({} characters; max is {MAX_META_FIELD_LENGTH})",
-                text.len()
-            ),
-        }
-        .into());
+        return Err(source.error(
+            Error::InvalidMeta {
+                field: name.to_owned(),
+                reason: format!(
+                    "too long ({} characters; max is {MAX_META_FIELD_LENGTH})",
+                    text.len()
+                ),
+            },
+            source.value(&["meta", name]),
+            None,
+        ));
     }
 
     Ok(())
 }
 
+fn undefined_swatch(
+    role: &str,
+    swatch: &str,
+    palette: &Palette,
+    location: Option<&Location>,
+) -> crate::Error {
+    Diagnostic {
+        location: location.cloned(),
+        help: did_you_mean(
+            swatch,
+            palette.into_iter().map(|s| s.name.as_str()),
+        )
+        .map(|name| format!("did you mean `${name}`?")),
+    }
+    .attach(Error::UndefinedSwatch {
+        role: role.to_owned(),
+        swatch: swatch.to_owned(),
+    })
+}
+
+/// Guesses what a role value that's neither a role nor a valid `$swatch` was
+/// meant to be.
+fn suggest_value(value: &str, palette: &Palette) -> Option<String> {
+    if let Some(swatch) = value.strip_prefix('$') {
+        return did_you_mean(
+            swatch,
+            palette.into_iter().map(|s| s.name.as_str()),
+        )
+        .map(|name| format!("did you mean `${name}`?"));
+    }
+
+    if palette.get(value).is_some() {
+        return Some(format!("did you mean `${value}`?"));
+    }
+
+    did_you_mean(value, roles::iter().map(|r| r.as_str()))
+        .map(|role| format!("did you mean `{role}`?"))
+}
+
 #[cfg(test)]
 mod tests {
     use std::collections::BTreeMap;
     use std::io::Write as _;
 
     use indoc::indoc;
+    use itertools::Itertools as _;
     use minijinja::Value as JinjaValue;
     use tempfile::NamedTempFile;
 
@@ -657,4 +806,61 @@ mod tests {
             white = "$white"
             "##}
     }
+
+    // every other required role points at `$white`, so only the `[roles.ansi]`
+    // lines above can break
+    fn complete_scheme() -> String {
+        let roles = roles::base()
+            .filter(|role| !role.as_str().starts_with("ansi."))
+            .map(|role| format!("{role} = \"$white\""))
+            .join("\n");
+
+        format!("{}\n[roles]\n{roles}\n", minimal_valid_scheme())
+    }
+
+    fn load_error(toml: &str) -> String {
+        match scheme_from_toml("broken", toml) {
+            Ok(_) => panic!("scheme should fail to load"),
+            Err(e) => e.to_string(),
+        }
+    }
+
+    #[test]
+    fn suggests_swatch_prefix_for_bare_swatch_names() {
+        let toml = complete_scheme().replace(r#""$green""#, r#""green""#);
+        let error = load_error(&toml);
+
+        assert!(
+            error.contains("did you mean `$green`?"),
+            "missing suggestion in:\n{error}"
+        );
+        assert!(
+            error.contains(r#"green = "green""#),
+            "missing snippet in:\n{error}"
+        );
+    }
+
+    #[test]
+    fn locates_undefined_swatches() {
+        let toml = complete_scheme().replace(r#""$blue""#, r#""$bleu""#);
+        let error = load_error(&toml);
+
+        assert!(error.contains(":16:8"), "wrong location in:\n{error}");
+        assert!(
+            error.contains("did you mean `$blue`?"),
+            "missing suggestion in:\n{error}"
+        );
+    }
+
+    #[test]
+    fn suggests_role_names() {
+        let toml =
+            complete_scheme().replace(r#"magenta = "$"#, r#"magneta = "$"#);
+        let error = load_error(&toml);
+
+        assert!(
+            error.contains("did you mean `ansi.magenta`?"),
+            "missing suggestion in:\n{error}"
+        );
+    }
 }